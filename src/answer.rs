@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+/// Answer to a puzzle part, as it would be submitted
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+    /// The solver didn't find any answer
+    Missing,
+    /// The solver failed
+    Error(String),
+}
+
+impl Answer {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Answer::Error(_))
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Missing => write!(f, "no answer"),
+            Answer::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+macro_rules! answer_from_integer {
+    ($($t: ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Self {
+                Answer::Number(n as i128)
+            }
+        })*
+    };
+}
+
+answer_from_integer!(i32, i64, isize, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_owned())
+    }
+}
+
+impl<T: Into<Answer>> From<Option<T>> for Answer {
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Answer::Missing, Into::into)
+    }
+}
+
+impl<T: Into<Answer>, E: Display> From<Result<T, E>> for Answer {
+    fn from(r: Result<T, E>) -> Self {
+        r.map_or_else(|e| Answer::Error(e.to_string()), Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_display() {
+        assert_eq!(Answer::from(Some(514579)).to_string(), "514579");
+        assert_eq!(
+            Answer::from(Ok::<isize, String>(-1753)).to_string(),
+            "-1753"
+        );
+        assert_eq!(Answer::from("67384529".to_string()).to_string(), "67384529");
+        assert_eq!(Answer::from(None::<usize>).to_string(), "no answer");
+        assert_eq!(
+            Answer::from(Err::<usize, _>("invalid operation")).to_string(),
+            "error: invalid operation"
+        );
+    }
+
+    #[test]
+    pub fn test_compare() {
+        assert_eq!(Answer::from(5_usize), Answer::from(Some(5_i64)));
+        assert_ne!(Answer::from(5_usize), Answer::from("5"));
+        assert!(Answer::from(Err::<usize, _>("oops")).is_error());
+        assert!(!Answer::from(None::<usize>).is_error());
+    }
+}
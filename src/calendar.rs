@@ -0,0 +1,133 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Utc};
+
+/// First Advent of Code event
+pub const FIRST_YEAR: u32 = 2015;
+/// Last puzzle of an event
+pub const LAST_DAY: u32 = 25;
+
+/// Puzzles unlock at midnight EST (UTC-5)
+fn timezone() -> FixedOffset {
+    FixedOffset::west(5 * 3600)
+}
+
+pub fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&timezone())
+}
+
+pub fn is_unlocked(year: u32, day: u32, now: &DateTime<FixedOffset>) -> bool {
+    year >= FIRST_YEAR
+        && (1..=LAST_DAY).contains(&day)
+        && *now >= timezone().ymd(year as i32, 12, day).and_hms(0, 0, 0)
+}
+
+/// Latest event that has started
+pub fn default_year(now: &DateTime<FixedOffset>) -> u32 {
+    if now.month() == 12 {
+        now.year() as u32
+    } else {
+        now.year() as u32 - 1
+    }
+}
+
+/// Days of `year` unlocked at `now`
+pub fn unlocked_days(year: u32, now: &DateTime<FixedOffset>) -> Vec<u32> {
+    (1..=LAST_DAY)
+        .filter(|&d| is_unlocked(year, d, now))
+        .collect()
+}
+
+/// Day of an event, as given on the command line
+pub fn parse_day(s: &str) -> Result<u32, String> {
+    let day = s
+        .trim()
+        .parse::<u32>()
+        .map_err(|e| format!("invalid day '{}': {}", s, e))?;
+
+    if (1..=LAST_DAY).contains(&day) {
+        Ok(day)
+    } else {
+        Err(format!("invalid day: {}, expected 1 to {}", day, LAST_DAY))
+    }
+}
+
+/// Single day or inclusive range of days, as given on the command line (`3` or `1-10`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct DayRange(RangeInclusive<u32>);
+
+impl DayRange {
+    pub fn days(&self) -> RangeInclusive<u32> {
+        self.0.clone()
+    }
+}
+
+impl FromStr for DayRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bounds = s.splitn(2, '-');
+        let start = parse_day(bounds.next().unwrap_or_default())?;
+        let end = match bounds.next() {
+            Some(end) => parse_day(end)?,
+            None => start,
+        };
+
+        if start > end {
+            Err(format!("invalid day range: {}", s))
+        } else {
+            Ok(DayRange(start..=end))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<FixedOffset> {
+        timezone().ymd(year, month, day).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    pub fn test_default_year() {
+        assert_eq!(default_year(&at(2020, 12, 1, 0)), 2020);
+        assert_eq!(default_year(&at(2021, 6, 15, 12)), 2020);
+        assert_eq!(default_year(&at(2021, 11, 30, 23)), 2020);
+    }
+
+    #[test]
+    pub fn test_unlocked_days() {
+        assert_eq!(
+            unlocked_days(2020, &at(2020, 11, 30, 23)),
+            Vec::<u32>::new()
+        );
+        assert_eq!(unlocked_days(2020, &at(2020, 12, 3, 0)), [1, 2, 3]);
+        assert_eq!(unlocked_days(2020, &at(2021, 6, 15, 12)).len(), 25);
+        assert!(!is_unlocked(2014, 1, &at(2021, 6, 15, 12)));
+        assert!(!is_unlocked(2020, 26, &at(2021, 6, 15, 12)));
+
+        // Midnight UTC is still the previous day in EST
+        let utc = FixedOffset::east(0).ymd(2020, 12, 4).and_hms(0, 0, 0);
+        assert!(!is_unlocked(2020, 4, &utc.with_timezone(&timezone())));
+    }
+
+    #[test]
+    pub fn test_day_range() {
+        assert_eq!("3".parse(), Ok(DayRange(3..=3)));
+        assert_eq!("1-10".parse(), Ok(DayRange(1..=10)));
+        assert!("0".parse::<DayRange>().is_err());
+        assert!("10-1".parse::<DayRange>().is_err());
+        assert!("20-26".parse::<DayRange>().is_err());
+        assert!("a-3".parse::<DayRange>().is_err());
+    }
+
+    #[test]
+    pub fn test_parse_day() {
+        assert_eq!(parse_day("7"), Ok(7));
+        assert_eq!(parse_day("25"), Ok(25));
+        assert!(parse_day("0").is_err());
+        assert!(parse_day("26").is_err());
+        assert!(parse_day("x").is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::answer::Answer;
+
+/// Directory holding one `{year}.toml` file of expected answers per year
+pub const ANSWERS_DIR: &str = "./answers";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CheckError {
+    #[error("couldn't read {0}: {1}")]
+    IoError(PathBuf, String),
+    #[error("invalid answers file: {0}")]
+    InvalidFile(String),
+}
+
+/// Outcome of comparing an answer with the expected one
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    /// Holds the expected answer
+    Fail(String),
+    /// No answer is recorded for this part
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Fail(expected) => write!(f, "FAIL, expected {}", expected),
+            Verdict::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DayAnswers {
+    part1: Option<String>,
+    part2: Option<String>,
+}
+
+/// Known answers of a year, read from a file such as:
+/// ```toml
+/// [1]
+/// part1 = "514579"
+/// part2 = "241861950"
+/// ```
+#[derive(Debug, Default)]
+pub struct ExpectedAnswers {
+    answers: HashMap<(u32, u8), String>,
+}
+
+impl ExpectedAnswers {
+    /// Loads `{dir}/{year}.toml`, a missing file meaning every answer is unknown
+    pub fn load(dir: &Path, year: u32) -> Result<ExpectedAnswers, CheckError> {
+        let path = dir.join(format!("{}.toml", year));
+
+        match fs::read_to_string(&path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(ExpectedAnswers::default()),
+            Err(e) => Err(CheckError::IoError(path, e.to_string())),
+        }
+    }
+
+    pub fn verdict(&self, day: u32, part: u8, answer: &Answer) -> Verdict {
+        match self.answers.get(&(day, part)) {
+            Some(expected) if !answer.is_error() && answer.to_string() == *expected => {
+                Verdict::Pass
+            }
+            Some(expected) => Verdict::Fail(expected.clone()),
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+impl FromStr for ExpectedAnswers {
+    type Err = CheckError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let days: BTreeMap<String, DayAnswers> =
+            toml::from_str(s).map_err(|e| CheckError::InvalidFile(e.to_string()))?;
+
+        let mut answers = HashMap::new();
+        for (day, parts) in days {
+            let day = day
+                .parse()
+                .map_err(|_| CheckError::InvalidFile(format!("invalid day: {}", day)))?;
+
+            if let Some(a) = parts.part1 {
+                answers.insert((day, 1), a);
+            }
+            if let Some(a) = parts.part2 {
+                answers.insert((day, 2), a);
+            }
+        }
+
+        Ok(ExpectedAnswers { answers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        days,
+        inputs::{cache::InputCache, InputError, InputFetcher, INPUT_DIR},
+    };
+
+    #[test]
+    pub fn test_verdict() {
+        let expected: ExpectedAnswers = "[1]
+part1 = \"514579\"
+
+[8]
+part1 = \"5\"
+part2 = \"8\""
+            .parse()
+            .unwrap();
+
+        assert_eq!(expected.verdict(1, 1, &Some(514579).into()), Verdict::Pass);
+        assert_eq!(expected.verdict(1, 2, &Some(1).into()), Verdict::Unknown);
+        assert_eq!(
+            expected.verdict(8, 2, &Ok::<_, String>(9).into()),
+            Verdict::Fail("8".to_string())
+        );
+        assert_eq!(
+            expected.verdict(8, 1, &Err::<usize, _>("5").into()),
+            Verdict::Fail("5".to_string())
+        );
+        assert!("[day1]\npart1 = \"1\"".parse::<ExpectedAnswers>().is_err());
+    }
+
+    /// Days too slow to solve in debug builds, checked with `--check` instead
+    const SLOW_DAYS: &[(u32, u32)] = &[(2020, 15), (2020, 23)];
+
+    /// Checks every recorded answer against the inputs in `INPUT_DIR`, skipping days
+    /// without an input
+    #[test]
+    pub fn test_expected_answers() {
+        let cache = InputCache::new(INPUT_DIR);
+        let mut failures = vec![];
+
+        for solution in days::registry().iter() {
+            let (year, day) = (solution.year(), solution.day());
+            if SLOW_DAYS.contains(&(year, day)) {
+                continue;
+            }
+
+            let expected = ExpectedAnswers::load(Path::new(ANSWERS_DIR), year).unwrap();
+            let input = match cache.fetch(year, day) {
+                Ok(input) => input,
+                Err(InputError::NotCached(..)) => continue,
+                Err(e) => panic!("Couldn't read input of {} day {}: {}", year, day, e),
+            };
+
+            for &part in &[1, 2] {
+                let answer = match part {
+                    1 => solution.part1(&input),
+                    _ => solution.part2(&input),
+                };
+
+                if let Verdict::Fail(e) = expected.verdict(day, part, &answer) {
+                    failures.push(format!(
+                        "{} day {} part {}: got {}, expected {}",
+                        year, day, part, answer, e
+                    ));
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+}
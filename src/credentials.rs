@@ -0,0 +1,192 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+/// Environment variable holding the adventofcode.com session token
+pub const SESSION_ENV_VAR: &str = "AOC_SESSION";
+
+#[derive(Debug, Error, PartialEq, Clone)]
+pub enum CredentialError {
+    #[error(
+        "no session token found: set ${}, write it to ~/.config/aoc/session or pass --session-file",
+        SESSION_ENV_VAR
+    )]
+    NotFound,
+    #[error("couldn't read session file {0}: {1}")]
+    Unreadable(PathBuf, String),
+    #[error("session file {0} is empty")]
+    Empty(PathBuf),
+}
+
+/// Session token used to authenticate against adventofcode.com
+#[derive(Debug, PartialEq, Clone)]
+pub struct Session(String);
+
+impl Session {
+    pub fn new(token: &str) -> Session {
+        Session(token.trim().to_owned())
+    }
+
+    /// Value of the `Cookie` header expected by adventofcode.com
+    pub fn cookie(&self) -> String {
+        format!("session={}", self.0)
+    }
+}
+
+/// Looks up the session token from, in that order:
+/// 1. the `AOC_SESSION` environment variable,
+/// 2. the `~/.config/aoc/session` file,
+/// 3. the file given with `--session-file`.
+pub struct CredentialProvider {
+    env_token: Option<String>,
+    config_file: Option<PathBuf>,
+    session_file: Option<PathBuf>,
+}
+
+impl CredentialProvider {
+    pub fn new(session_file: Option<PathBuf>) -> CredentialProvider {
+        CredentialProvider {
+            env_token: env::var(SESSION_ENV_VAR).ok(),
+            config_file: default_config_file(),
+            session_file,
+        }
+    }
+
+    /// Provider that always yields the given token
+    #[cfg(test)]
+    pub fn with_token(token: &str) -> CredentialProvider {
+        CredentialProvider {
+            env_token: Some(token.to_owned()),
+            config_file: None,
+            session_file: None,
+        }
+    }
+
+    pub fn session(&self) -> Result<Session, CredentialError> {
+        if let Some(token) = &self.env_token {
+            if !token.trim().is_empty() {
+                return Ok(Session::new(token));
+            }
+        }
+
+        // The config file is optional, only use it if it exists
+        let config = match &self.config_file {
+            Some(path) if path.is_file() => Some(read_session_file(path)),
+            _ => None,
+        };
+
+        match (config, &self.session_file) {
+            (Some(Ok(session)), _) => Ok(session),
+            // An unusable config file doesn't hide the file passed explicitly
+            (_, Some(path)) => read_session_file(path),
+            (Some(Err(e)), None) => Err(e),
+            (None, None) => Err(CredentialError::NotFound),
+        }
+    }
+}
+
+fn default_config_file() -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config_dir.join("aoc").join("session"))
+}
+
+fn read_session_file(path: &Path) -> Result<Session, CredentialError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| CredentialError::Unreadable(path.to_owned(), e.to_string()))?;
+
+    if content.trim().is_empty() {
+        Err(CredentialError::Empty(path.to_owned()))
+    } else {
+        Ok(Session::new(&content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    /// Path of a temporary file, unique to the test process
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("aoc-credentials-{}-{}", process::id(), name))
+    }
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = temp_path(name);
+        fs::write(&path, content).expect("Couldn't write temporary file");
+        path
+    }
+
+    #[test]
+    pub fn test_lookup_order() {
+        let config = temp_file("order-config", "from-config\n");
+        let flag = temp_file("order-flag", "from-flag\n");
+
+        let provider = CredentialProvider {
+            env_token: Some("from-env".to_string()),
+            config_file: Some(config.clone()),
+            session_file: Some(flag.clone()),
+        };
+        assert_eq!(provider.session(), Ok(Session::new("from-env")));
+
+        let provider = CredentialProvider {
+            env_token: None,
+            config_file: Some(config),
+            session_file: Some(flag.clone()),
+        };
+        assert_eq!(provider.session(), Ok(Session::new("from-config")));
+
+        let provider = CredentialProvider {
+            env_token: Some("  ".to_string()),
+            config_file: Some(temp_path("missing")),
+            session_file: Some(flag.clone()),
+        };
+        assert_eq!(provider.session(), Ok(Session::new("from-flag")));
+
+        // An empty config file falls through to --session-file
+        let empty = temp_file("order-empty", "\n");
+        let provider = CredentialProvider {
+            env_token: None,
+            config_file: Some(empty.clone()),
+            session_file: Some(flag),
+        };
+        assert_eq!(provider.session(), Ok(Session::new("from-flag")));
+
+        let provider = CredentialProvider {
+            env_token: None,
+            config_file: Some(empty.clone()),
+            session_file: None,
+        };
+        assert_eq!(provider.session(), Err(CredentialError::Empty(empty)));
+    }
+
+    #[test]
+    pub fn test_missing_token() {
+        let provider = CredentialProvider {
+            env_token: None,
+            config_file: None,
+            session_file: None,
+        };
+        assert_eq!(provider.session(), Err(CredentialError::NotFound));
+
+        let empty = temp_file("empty", "\n");
+        let provider = CredentialProvider {
+            env_token: None,
+            config_file: None,
+            session_file: Some(empty.clone()),
+        };
+        assert_eq!(provider.session(), Err(CredentialError::Empty(empty)));
+    }
+
+    #[test]
+    pub fn test_cookie() {
+        assert_eq!(Session::new("abc123\n").cookie(), "session=abc123");
+    }
+}
@@ -1,2 +1,14 @@
 pub mod y2019;
 pub mod y2020;
+
+use crate::solution::Registry;
+
+/// Registry holding the solutions of every year
+pub fn registry() -> Registry {
+    let mut registry = Registry::default();
+
+    y2019::register(&mut registry);
+    y2020::register(&mut registry);
+
+    registry
+}
@@ -0,0 +1,3 @@
+use crate::solution::Registry;
+
+pub fn register(_registry: &mut Registry) {}
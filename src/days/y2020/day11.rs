@@ -1,191 +1,252 @@
-pub fn solve_part1(input: &str) -> usize {
-    let s = solve(input, false);
+use std::{convert::TryFrom, fmt, mem};
 
-    s.lines()
-        .map(|l| l.chars().filter(|&c| c == '#').count())
-        .sum()
-}
+use rayon::prelude::*;
+
+use crate::utils::grid::{Grid, GridError, Point, DIRECTIONS8};
 
-pub fn solve_part2(input: &str) -> usize {
-    let s = solve(input, true);
+/// Number of seats updated at once by a thread
+const CHUNK: usize = 4096;
 
-    s.lines()
-        .map(|l| l.chars().filter(|&c| c == '#').count())
-        .sum()
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Seat {
+    Floor,
+    Empty,
+    Occupied,
 }
 
-fn solve(input: &str, remote_check: bool) -> String {
-    let mut s = step(input, remote_check);
-    loop {
-        let last = s.clone();
-        s = step(&last, remote_check);
+impl TryFrom<char> for Seat {
+    type Error = char;
 
-        if s == last {
-            break;
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '.' => Ok(Seat::Floor),
+            'L' => Ok(Seat::Empty),
+            '#' => Ok(Seat::Occupied),
+            _ => Err(value),
         }
     }
+}
 
-    s
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Seat::Floor => '.',
+            Seat::Empty => 'L',
+            Seat::Occupied => '#',
+        };
+
+        write!(f, "{}", c)
+    }
 }
 
-fn step(input: &str, remote_check: bool) -> String {
-    let height = input.lines().count();
-    let width = input
-        .lines()
-        .next()
-        .expect("No lines to step on")
-        .chars()
-        .count();
+/// Seats taken into account when deciding whether a seat changes
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Visibility {
+    /// The 8 adjacent seats, 4 of them being occupied making people leave
+    Adjacent,
+    /// The first seat seen in each of the 8 directions, 5 of them being needed
+    LineOfSight,
+}
 
-    let mut s = String::new();
+/// Seating system, where the neighbours of every seat are computed once. Floor cells never
+/// change and are left out of the simulation.
+pub struct Simulation {
+    layout: Grid<Seat>,
+    /// Position in `layout` of each seat
+    positions: Vec<Point>,
+    /// Neighbours of each seat. Missing ones point to an extra seat that is never occupied,
+    /// so that every seat can be handled the same way.
+    neighbours: Vec<[u32; 8]>,
+    tolerance: u8,
+    /// Occupation of each seat (1 if occupied), and the buffer the next generation is written to
+    occupied: Vec<u8>,
+    next: Vec<u8>,
+    /// Number of seats that changed at each generation
+    changes: Vec<usize>,
+}
 
-    for y in 0..height {
-        for x in 0..width {
-            s.push(get_updated_seat(input, x, y, remote_check, (width, height)));
+impl Simulation {
+    pub fn new(layout: Grid<Seat>, visibility: Visibility) -> Simulation {
+        let positions: Vec<_> = layout
+            .iter()
+            .filter(|(_, &seat)| seat != Seat::Floor)
+            .map(|(position, _)| position)
+            .collect();
+
+        // Seat ids, by cell
+        let mut ids = layout.map(|_, _| None);
+        for (id, &position) in positions.iter().enumerate() {
+            *ids.get_mut(position).unwrap() = Some(id as u32);
         }
 
-        s.push('\n');
-    }
+        let nobody = positions.len() as u32;
+
+        let neighbours = positions
+            .iter()
+            .map(|&position| {
+                let seen: Vec<Point> = match visibility {
+                    Visibility::Adjacent => layout.neighbours8(position).map(|(p, _)| p).collect(),
+                    Visibility::LineOfSight => DIRECTIONS8
+                        .iter()
+                        .filter_map(|&direction| {
+                            layout
+                                .ray(position, direction)
+                                .find(|(_, &seat)| seat != Seat::Floor)
+                                .map(|(p, _)| p)
+                        })
+                        .collect(),
+                };
+
+                let mut neighbours = [nobody; 8];
+                for (neighbour, id) in neighbours
+                    .iter_mut()
+                    .zip(seen.iter().filter_map(|&p| *ids.get(p).unwrap()))
+                {
+                    *neighbour = id;
+                }
 
-    s.trim_end().to_owned()
-}
+                neighbours
+            })
+            .collect();
+
+        let occupied: Vec<u8> = positions
+            .iter()
+            .map(|&p| (layout.get(p) == Some(&Seat::Occupied)) as u8)
+            .chain(std::iter::once(0))
+            .collect();
+
+        Simulation {
+            tolerance: match visibility {
+                Visibility::Adjacent => 4,
+                Visibility::LineOfSight => 5,
+            },
+            next: occupied.clone(),
+            occupied,
+            layout,
+            positions,
+            neighbours,
+            changes: vec![],
+        }
+    }
 
-fn get_updated_seat(
-    input: &str,
-    x: usize,
-    y: usize,
-    remote_check: bool,
-    size: (usize, usize),
-) -> char {
-    match input.lines().nth(y) {
-        Some(l) => {
-            let seat = l
-                .chars()
-                .nth(x)
-                .unwrap_or_else(|| panic!("Invalid column index: {}", x));
-            if seat != 'L' && seat != '#' && seat != '.' {
-                panic!("Invalid seat: {}", seat);
-            }
-
-            let neighbours = get_neighbours(input, (x, y), remote_check, size);
-
-            match seat {
-                '#' => {
-                    let check = match remote_check {
-                        true => 5,
-                        false => 4,
+    /// Computes the next generation, returning the number of seats that changed
+    pub fn step(&mut self) -> usize {
+        let occupied = &self.occupied;
+        let tolerance = self.tolerance;
+
+        let changed = self
+            .next
+            .par_chunks_mut(CHUNK)
+            .zip(occupied.par_chunks(CHUNK))
+            .zip(self.neighbours.par_chunks(CHUNK))
+            .map(|((next, current), neighbours)| {
+                let mut changed = 0;
+                for ((next, &current), neighbours) in next.iter_mut().zip(current).zip(neighbours) {
+                    let count: u8 = neighbours.iter().map(|&n| occupied[n as usize]).sum();
+                    *next = if current == 1 {
+                        (count < tolerance) as u8
+                    } else {
+                        (count == 0) as u8
                     };
 
-                    if neighbours >= check {
-                        'L'
-                    } else {
-                        '#'
-                    }
-                }
-                'L' => {
-                    if neighbours == 0 {
-                        '#'
-                    } else {
-                        'L'
-                    }
+                    changed += (*next != current) as usize;
                 }
-                '.' => '.',
-                _ => panic!("Invalid seat: {}", seat),
-            }
-        }
-        None => panic!("Invalid row index: {}", y),
+
+                changed
+            })
+            .sum();
+
+        mem::swap(&mut self.occupied, &mut self.next);
+        self.changes.push(changed);
+        changed
     }
-}
 
-fn get_neighbours(
-    input: &str,
-    pos: (usize, usize),
-    remote_check: bool,
-    size: (usize, usize),
-) -> usize {
-    let mut neighbours = 0;
-    let (pos_x, pos_y) = pos;
-
-    let directions = [
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, -1),
-        (0, 1),
-        (1, -1),
-        (1, 0),
-        (1, 1),
-    ];
-
-    directions.iter().for_each(|(x, y)| {
-        if let Some(c) = get_next_in_direction(
-            input,
-            size,
-            (pos_x as isize, pos_y as isize),
-            (*x, *y),
-            remote_check,
-        ) {
-            if c == '#' {
-                neighbours += 1;
-            }
+    /// Steps until no seat changes anymore, returning the number of occupied seats
+    pub fn run(&mut self) -> usize {
+        while self.changes().last() != Some(&0) {
+            self.step();
         }
-    });
 
-    neighbours
+        self.occupied()
+    }
+
+    /// Number of generations computed so far
+    #[allow(dead_code)]
+    pub fn generation(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Number of seats that changed at each generation
+    pub fn changes(&self) -> &[usize] {
+        &self.changes
+    }
+
+    pub fn occupied(&self) -> usize {
+        self.occupied.iter().filter(|&&o| o == 1).count()
+    }
 }
 
-fn get_next_in_direction(
-    input: &str,
-    size: (usize, usize),
-    pos: (isize, isize),
-    direction: (isize, isize),
-    remote_check: bool,
-) -> Option<char> {
-    let (posx, posy) = pos;
-    let (directionx, directiony) = direction;
-
-    let next = (posx.checked_add(directionx), posy.checked_add(directiony));
-
-    match next {
-        (Some(x), Some(y)) => {
-            if x < 0 || y < 0 {
-                return None;
-            }
-
-            let x = x as usize;
-            let y = y as usize;
-
-            if x >= size.0 || y >= size.1 {
-                return None;
-            }
-
-            match input.lines().nth(y).unwrap().chars().nth(x).unwrap() {
-                '.' => {
-                    if remote_check {
-                        get_next_in_direction(
-                            input,
-                            size,
-                            (x as isize, y as isize),
-                            direction,
-                            remote_check,
-                        )
-                    } else {
-                        Some('.')
-                    }
-                }
-                seat @ 'L' | seat @ '#' => Some(seat),
-                seat => panic!("Invalid seat: {}", seat),
-            }
+/// Current state of the seating area
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut layout = self.layout.clone();
+        for (&position, &occupied) in self.positions.iter().zip(&self.occupied) {
+            *layout.get_mut(position).unwrap() = if occupied == 1 {
+                Seat::Occupied
+            } else {
+                Seat::Empty
+            };
         }
-        _ => None,
+
+        write!(f, "{}", layout)
     }
 }
 
-#[test]
-pub fn test_step() {
-    assert_eq!(
-        step(
-            "L.LL.LL.LL
+pub fn solve_part1(input: &str) -> Result<usize, GridError> {
+    Ok(Simulation::new(parse(input)?, Visibility::Adjacent).run())
+}
+
+pub fn solve_part2(input: &str) -> Result<usize, GridError> {
+    Ok(Simulation::new(parse(input)?, Visibility::LineOfSight).run())
+}
+
+pub fn parse(input: &str) -> Result<Grid<Seat>, GridError> {
+    input.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulation(input: &str, remote_check: bool) -> Simulation {
+        let visibility = if remote_check {
+            Visibility::LineOfSight
+        } else {
+            Visibility::Adjacent
+        };
+
+        Simulation::new(parse(input).unwrap(), visibility)
+    }
+
+    fn solve(input: &str, remote_check: bool) -> String {
+        let mut simulation = simulation(input, remote_check);
+        simulation.run();
+
+        simulation.to_string()
+    }
+
+    fn step(input: &str, remote_check: bool) -> String {
+        let mut simulation = simulation(input, remote_check);
+        simulation.step();
+
+        simulation.to_string()
+    }
+
+    #[test]
+    pub fn test_step() {
+        assert_eq!(
+            step(
+                "L.LL.LL.LL
 LLLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -195,9 +256,9 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL",
-            false
-        ),
-        "#.##.##.##
+                false
+            ),
+            "#.##.##.##
 #######.##
 #.#.#..#..
 ####.##.##
@@ -207,12 +268,12 @@ L.LLLLL.LL",
 ##########
 #.######.#
 #.#####.##"
-            .to_string()
-    );
+                .to_string()
+        );
 
-    assert_eq!(
-        step(
-            "#.##.##.##
+        assert_eq!(
+            step(
+                "#.##.##.##
 #######.##
 #.#.#..#..
 ####.##.##
@@ -222,9 +283,9 @@ L.LLLLL.LL",
 ##########
 #.######.#
 #.#####.##",
-            false
-        ),
-        "#.LL.L#.##
+                false
+            ),
+            "#.LL.L#.##
 #LLLLLL.L#
 L.L.L..L..
 #LLL.LL.L#
@@ -234,11 +295,11 @@ L.L.L..L..
 #LLLLLLLL#
 #.LLLLLL.L
 #.#LLLL.##"
-    );
+        );
 
-    assert_eq!(
-        step(
-            "#.LL.L#.##
+        assert_eq!(
+            step(
+                "#.LL.L#.##
 #LLLLLL.L#
 L.L.L..L..
 #LLL.LL.L#
@@ -248,9 +309,9 @@ L.L.L..L..
 #LLLLLLLL#
 #.LLLLLL.L
 #.#LLLL.##",
-            false
-        ),
-        "#.##.L#.##
+                false
+            ),
+            "#.##.L#.##
 #L###LL.L#
 L.#.#..#..
 #L##.##.L#
@@ -260,11 +321,11 @@ L.#.#..#..
 #L######L#
 #.LL###L.L
 #.#L###.##"
-    );
+        );
 
-    assert_eq!(
-        step(
-            "#.##.L#.##
+        assert_eq!(
+            step(
+                "#.##.L#.##
 #L###LL.L#
 L.#.#..#..
 #L##.##.L#
@@ -274,9 +335,9 @@ L.#.#..#..
 #L######L#
 #.LL###L.L
 #.#L###.##",
-            false
-        ),
-        "#.#L.L#.##
+                false
+            ),
+            "#.#L.L#.##
 #LLL#LL.L#
 L.L.L..#..
 #LLL.##.L#
@@ -286,11 +347,11 @@ L.L.L..#..
 #L#LLLL#L#
 #.LLLLLL.L
 #.#L#L#.##"
-    );
+        );
 
-    assert_eq!(
-        step(
-            "#.#L.L#.##
+        assert_eq!(
+            step(
+                "#.#L.L#.##
 #LLL#LL.L#
 L.L.L..#..
 #LLL.##.L#
@@ -300,9 +361,9 @@ L.L.L..#..
 #L#LLLL#L#
 #.LLLLLL.L
 #.#L#L#.##",
-            false
-        ),
-        "#.#L.L#.##
+                false
+            ),
+            "#.#L.L#.##
 #LLL#LL.L#
 L.#.L..#..
 #L##.##.L#
@@ -312,14 +373,14 @@ L.#.L..#..
 #L#L##L#L#
 #.LLLLLL.L
 #.#L#L#.##"
-    );
-}
+        );
+    }
 
-#[test]
-pub fn test_solve() {
-    assert_eq!(
-        solve(
-            "L.LL.LL.LL
+    #[test]
+    pub fn test_solve() {
+        assert_eq!(
+            solve(
+                "L.LL.LL.LL
 LLLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -329,9 +390,9 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL",
-            false
-        ),
-        "#.#L.L#.##
+                false
+            ),
+            "#.#L.L#.##
 #LLL#LL.L#
 L.#.L..#..
 #L##.##.L#
@@ -341,14 +402,14 @@ L.#.L..#..
 #L#L##L#L#
 #.LLLLLL.L
 #.#L#L#.##"
-    );
-}
+        );
+    }
 
-#[test]
-pub fn test_step_no_proximity() {
-    assert_eq!(
-        step(
-            "L.LL.LL.LL
+    #[test]
+    pub fn test_step_no_proximity() {
+        assert_eq!(
+            step(
+                "L.LL.LL.LL
 LLLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -358,9 +419,9 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL",
-            true
-        ),
-        "#.##.##.##
+                true
+            ),
+            "#.##.##.##
 #######.##
 #.#.#..#..
 ####.##.##
@@ -370,10 +431,10 @@ L.LLLLL.LL",
 ##########
 #.######.#
 #.#####.##"
-    );
-    assert_eq!(
-        step(
-            "#.##.##.##
+        );
+        assert_eq!(
+            step(
+                "#.##.##.##
 #######.##
 #.#.#..#..
 ####.##.##
@@ -383,9 +444,9 @@ L.LLLLL.LL",
 ##########
 #.######.#
 #.#####.##",
-            true
-        ),
-        "#.LL.LL.L#
+                true
+            ),
+            "#.LL.LL.L#
 #LLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -395,10 +456,10 @@ L.LLLLL.LL
 LLLLLLLLL#
 #.LLLLLL.L
 #.LLLLL.L#"
-    );
-    assert_eq!(
-        step(
-            "#.LL.LL.L#
+        );
+        assert_eq!(
+            step(
+                "#.LL.LL.L#
 #LLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -408,9 +469,9 @@ L.LLLLL.LL
 LLLLLLLLL#
 #.LLLLLL.L
 #.LLLLL.L#",
-            true
-        ),
-        "#.L#.##.L#
+                true
+            ),
+            "#.L#.##.L#
 #L#####.LL
 L.#.#..#..
 ##L#.##.##
@@ -420,10 +481,10 @@ L.#.#..#..
 LLL####LL#
 #.L#####.L
 #.L####.L#"
-    );
-    assert_eq!(
-        step(
-            "#.L#.##.L#
+        );
+        assert_eq!(
+            step(
+                "#.L#.##.L#
 #L#####.LL
 L.#.#..#..
 ##L#.##.##
@@ -433,9 +494,9 @@ L.#.#..#..
 LLL####LL#
 #.L#####.L
 #.L####.L#",
-            true
-        ),
-        "#.L#.L#.L#
+                true
+            ),
+            "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##LL.LL.L#
@@ -445,10 +506,10 @@ L.LL.LL.L#
 LLLLLLLLL#
 #.LLLLL#.L
 #.L#LL#.L#"
-    );
-    assert_eq!(
-        step(
-            "#.L#.L#.L#
+        );
+        assert_eq!(
+            step(
+                "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##LL.LL.L#
@@ -458,9 +519,9 @@ L.LL.LL.L#
 LLLLLLLLL#
 #.LLLLL#.L
 #.L#LL#.L#",
-            true
-        ),
-        "#.L#.L#.L#
+                true
+            ),
+            "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##L#.#L.L#
@@ -470,10 +531,10 @@ L.L#.#L.L#
 LLL###LLL#
 #.LLLLL#.L
 #.L#LL#.L#"
-    );
-    assert_eq!(
-        step(
-            "#.L#.L#.L#
+        );
+        assert_eq!(
+            step(
+                "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##L#.#L.L#
@@ -483,9 +544,9 @@ L.L#.#L.L#
 LLL###LLL#
 #.LLLLL#.L
 #.L#LL#.L#",
-            true
-        ),
-        "#.L#.L#.L#
+                true
+            ),
+            "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##L#.#L.L#
@@ -495,14 +556,14 @@ L.L#.LL.L#
 LLL###LLL#
 #.LLLLL#.L
 #.L#LL#.L#"
-    );
-}
+        );
+    }
 
-#[test]
-pub fn test_solve_no_proximity() {
-    assert_eq!(
-        solve(
-            "L.LL.LL.LL
+    #[test]
+    pub fn test_solve_no_proximity() {
+        assert_eq!(
+            solve(
+                "L.LL.LL.LL
 LLLLLLL.LL
 L.L.L..L..
 LLLL.LL.LL
@@ -512,9 +573,9 @@ L.LLLLL.LL
 LLLLLLLLLL
 L.LLLLLL.L
 L.LLLLL.LL",
-            true
-        ),
-        "#.L#.L#.L#
+                true
+            ),
+            "#.L#.L#.L#
 #LLLLLL.LL
 L.L.L..#..
 ##L#.#L.L#
@@ -524,5 +585,62 @@ L.L#.LL.L#
 LLL###LLL#
 #.LLLLL#.L
 #.L#LL#.L#"
-    );
+        );
+    }
+
+    #[test]
+    pub fn test_generations() {
+        let input = "L.LL.LL.LL
+LLLLLLL.LL
+L.L.L..L..
+LLLL.LL.LL
+L.LL.LL.LL
+L.LLLLL.LL
+..L.L.....
+LLLLLLLLLL
+L.LLLLLL.L
+L.LLLLL.LL";
+
+        // The seats stop changing after 5 steps, which the 6th one confirms
+        let mut seating = simulation(input, false);
+        assert_eq!(seating.run(), 37);
+        assert_eq!(seating.generation(), 6);
+        assert_eq!(seating.changes()[0], input.matches('L').count());
+        assert_eq!(seating.changes()[4..], [7, 0]);
+
+        let mut seating = simulation(input, true);
+        assert_eq!(seating.run(), 26);
+        assert_eq!(seating.generation(), 7);
+        assert_eq!(seating.changes().last(), Some(&0));
+
+        assert_eq!(seating.step(), 0);
+        assert_eq!(seating.generation(), 8);
+        assert_eq!(solve_part1(input), Ok(37));
+        assert_eq!(solve_part2(input), Ok(26));
+        assert!(matches!(
+            parse("L.\nLx"),
+            Err(GridError::InvalidCell {
+                line: 2,
+                column: 2,
+                c: 'x'
+            })
+        ));
+    }
+
+    #[test]
+    pub fn test_large_board() {
+        let row: String = (0..100)
+            .map(|x| if x % 7 == 3 { '.' } else { 'L' })
+            .collect();
+        let input = vec![row; 100].join("\n");
+
+        for &remote_check in &[false, true] {
+            let mut seating = simulation(&input, remote_check);
+            let occupied = seating.run();
+
+            assert!(occupied > 0);
+            assert_eq!(seating.step(), 0);
+            assert_eq!(seating.occupied(), occupied);
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use crate::utils::crt::chinese_remainder;
 
-fn generate_input(input: &str) -> (i64, Vec<(i64, i64)>) {
+pub fn generate_input(input: &str) -> (i64, Vec<(i64, i64)>) {
     let mut lines = input.lines();
     let starting_time = lines
         .next()
@@ -1,4 +1,4 @@
-fn generate_input(input: &str) -> Vec<usize> {
+pub fn generate_input(input: &str) -> Vec<usize> {
     input
         .chars()
         .flat_map(|c| c.to_digit(10))
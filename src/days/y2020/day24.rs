@@ -1,7 +1,112 @@
-pub fn solve_part1(_input: &str) -> usize {
-    0
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::utils::automaton::Automaton;
+
+/// Axial coordinates of a hexagonal tile
+type Tile = (i64, i64);
+
+const DAYS: usize = 100;
+
+/// Offsets of the adjacent tiles
+const NEIGHBOURS: [Tile; 6] = [(1, 0), (-1, 0), (1, -1), (0, -1), (0, 1), (-1, 1)];
+
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid direction in `{0}`")]
+pub struct InvalidPath(String);
+
+/// Tile reached by following `path` from the reference tile
+fn walk(path: &str) -> Result<Tile, InvalidPath> {
+    let mut tile = (0, 0);
+    let mut chars = path.chars();
+
+    while let Some(c) = chars.next() {
+        let (q, r) = match c {
+            'e' => (1, 0),
+            'w' => (-1, 0),
+            'n' | 's' => match (c, chars.next()) {
+                ('n', Some('e')) => (1, -1),
+                ('n', Some('w')) => (0, -1),
+                ('s', Some('e')) => (0, 1),
+                ('s', Some('w')) => (-1, 1),
+                _ => return Err(InvalidPath(path.to_owned())),
+            },
+            _ => return Err(InvalidPath(path.to_owned())),
+        };
+
+        tile = (tile.0 + q, tile.1 + r);
+    }
+
+    Ok(tile)
+}
+
+/// Tiles flipped an odd number of times, which end up black side up
+pub fn black_tiles(input: &str) -> Result<HashSet<Tile>, InvalidPath> {
+    let mut black = HashSet::new();
+    for line in input.lines() {
+        let tile = walk(line)?;
+        if !black.remove(&tile) {
+            black.insert(tile);
+        }
+    }
+
+    Ok(black)
+}
+
+pub fn solve_part1(input: &str) -> Result<usize, InvalidPath> {
+    Ok(black_tiles(input)?.len())
 }
 
-pub fn solve_part2(_input: &str) -> usize {
-    0
+pub fn solve_part2(input: &str) -> Result<usize, InvalidPath> {
+    let mut automaton = Automaton::new(
+        black_tiles(input)?,
+        "B2/S12".parse().expect("Invalid rules"),
+        NEIGHBOURS.to_vec(),
+    );
+
+    Ok(automaton.run(DAYS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "sesenwnenenewseeswwswswwnenewsewsw
+neeenesenwnwwswnenewnwwsewnenwseswesw
+seswneswswsenwwnwse
+nwnwneseeswswnenewneswwnewseswneseene
+swweswneswnenwsewnwneneseenw
+eesenwseswswnenwswnwnwsewwnwsene
+sewnenenenesenwsewnenwwwse
+wenwwweseeeweswwwnwwe
+wsweesenenewnwwnwsenewsenwwsesesenwne
+neeswseenwwswnwswswnw
+nenwswwsewswnenenewsenwsenwnesesenew
+enewnwewneswsewnwswenweswnenwsenwsw
+sweneswneswneneenwnewenewwneswswnese
+swwesenesewenwneswnwwneseswwne
+enesenwswwswneneswsenwnewswseenwsese
+wnwnesenesenenwwnenwsewesewsesesew
+nenewswnwewswnenesenwnesewesw
+eneswnwswnwsenenwnwnwwseeswneewsenese
+neswnwewnwnwseenwseesewsenwsweewe
+wseweeenwnesenwwwswnew";
+
+    #[test]
+    pub fn test_walk() {
+        assert_eq!(walk("nwwswee"), Ok((0, 0)));
+        assert_eq!(walk("esew"), Ok((0, 1)));
+        assert!(walk("nx").is_err());
+    }
+
+    #[test]
+    pub fn test_part1() {
+        assert_eq!(solve_part1(INPUT), Ok(10));
+    }
+
+    #[test]
+    pub fn test_part2() {
+        assert_eq!(solve_part2(INPUT), Ok(2208));
+    }
 }
@@ -1,7 +0,0 @@
-pub fn solve_part1(_input: &str) -> usize {
-    0
-}
-
-pub fn solve_part2(_input: &str) -> usize {
-    0
-}
@@ -1,74 +1,29 @@
-struct Map {
-    pub content: Vec<String>,
-    pub width: usize,
-    pub height: usize,
-}
-
-impl Map {
-    pub fn from(input: &str) -> Map {
-        let width = input.lines().next().expect("Couldn't get first line").len();
-        let height = input.lines().count();
-        let content = input.lines().map(|s| s.to_string()).collect();
-
-        Map {
-            content,
-            width,
-            height,
-        }
-    }
-
-    pub fn get(&self, pos: (usize, usize)) -> Option<char> {
-        let x = pos.0;
-        let y = pos.1;
+use crate::utils::grid::{Grid, GridError, Point};
 
-        if y >= self.height {
-            None
-        } else {
-            let index = x % self.width;
-            Some(
-                self.content[y]
-                    .chars()
-                    .nth(index)
-                    .unwrap_or_else(|| panic!("Couldn't access index {}", index)),
-            )
-        }
-    }
-
-    pub fn part1(&self, slope: (usize, usize)) -> usize {
-        let mut pos: (usize, usize) = (slope.0, slope.1);
-        let mut trees = 0;
-
-        while let Some(c) = self.get(pos) {
-            if c == '#' {
-                trees += 1;
-            }
+const TREE: char = '#';
 
-            pos.0 += slope.0;
-            pos.1 += slope.1;
-        }
-
-        trees
-    }
+/// Trees met going down the map along `slope`, the map repeating itself to the right
+fn count_trees(map: &Grid<char>, slope: Point) -> usize {
+    (1..)
+        .map(|i| (slope.0 * i, slope.1 * i))
+        .take_while(|&(_, y)| y < map.height() as isize)
+        .filter(|&position| *map.get_wrapping(position) == TREE)
+        .count()
 }
 
-pub fn solve_part1(input: &str) -> usize {
-    let input = Map::from(input);
+pub fn solve_part1(input: &str) -> Result<usize, GridError> {
+    let map = input.parse()?;
 
-    input.part1((3, 1))
+    Ok(count_trees(&map, (3, 1)))
 }
 
-pub fn solve_part2(input: &str) -> usize {
-    let input = Map::from(input);
+pub fn solve_part2(input: &str) -> Result<usize, GridError> {
+    let map = input.parse()?;
 
-    [
-        input.part1((1, 1)),
-        input.part1((3, 1)),
-        input.part1((5, 1)),
-        input.part1((7, 1)),
-        input.part1((1, 2)),
-    ]
-    .iter()
-    .product()
+    Ok([(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+        .iter()
+        .map(|&slope| count_trees(&map, slope))
+        .product())
 }
 
 #[cfg(test)]
@@ -91,7 +46,7 @@ mod tests {
 #...##....#
 .#..#...#.#"
             ),
-            7
+            Ok(7)
         );
         assert_eq!(
             solve_part2(
@@ -107,7 +62,7 @@ mod tests {
 #...##....#
 .#..#...#.#"
             ),
-            336
+            Ok(336)
         );
     }
 }
@@ -177,7 +177,7 @@ impl Passport {
     }
 }
 
-fn generate_input(input: &str) -> Vec<Result<Passport, Vec<PassportCreationError>>> {
+pub fn generate_input(input: &str) -> Vec<Result<Passport, Vec<PassportCreationError>>> {
     let mut v: Vec<Result<Passport, Vec<PassportCreationError>>> = vec![];
     let mut s = String::new();
 
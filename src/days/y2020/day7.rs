@@ -5,7 +5,7 @@ use itertools::Itertools;
 type Color = String;
 type Rules = HashMap<Color, Vec<(usize, Color)>>;
 
-fn parse_rules(input: &str) -> Rules {
+pub fn parse_rules(input: &str) -> Rules {
     input
         .lines()
         .map(|line| {
@@ -1,98 +1,39 @@
-use aoc_2020::vm::{instruction::Instruction, op::Op::*, *};
-use std::collections::HashSet;
+use aoc_2020::vm::{
+    analysis,
+    op::Op::*,
+    repair::{self, Mutation},
+    *,
+};
 
 pub fn solve_part1(input: &str) -> Result<isize, VmError> {
     let mut vm = Vm::from(input, false)?;
-    let mut repeat = HashSet::new();
 
-    while !repeat.contains(&vm.ip) {
-        repeat.insert(vm.ip);
-        vm.step();
-    }
-
-    Ok(vm.acc)
+    Ok(vm.run_until(StopCondition::InstructionRevisited)?.acc())
 }
 
 pub fn solve_part2(input: &str) -> Result<isize, VmError> {
-    let mut vm = Vm::from(input, false)?;
-    let mut repeat = HashSet::new();
-    let mut fixed = false;
-    let mut fixed_instructions: Vec<usize> = vec![];
-
-    loop {
-        let index = vm.ip;
-        let instruction = vm.instructions[index];
-        match instruction.op {
-            o @ Jmp | o @ Nop => {
-                if !fixed && !fixed_instructions.contains(&index) {
-                    let mut patched_instruction = Instruction::from(&instruction);
-                    if o == Jmp {
-                        patched_instruction.op = Nop;
-                    } else {
-                        patched_instruction.op = Jmp;
-                    }
-
-                    vm.patch_instruction(index, patched_instruction);
-                    fixed_instructions.push(index);
-                    fixed = true;
-                }
-            }
-            _ => {}
-        }
-
-        let r = vm.step();
-
-        if repeat.contains(&vm.ip) {
-            if fixed {
-                repeat.clear();
-                vm.reset();
-                fixed = false;
-            }
-        } else {
-            repeat.insert(vm.ip);
-        }
-
-        if r == VmState::Terminated {
-            break;
-        }
-    }
-
-    Ok(vm.acc)
+    let vm = Vm::from(input, false)?;
+    let mutations = [
+        Mutation::SwapOp { from: Jmp, to: Nop },
+        Mutation::SwapOp { from: Nop, to: Jmp },
+    ];
+
+    let patch = analysis::connecting_patches(&vm.instructions, &mutations)
+        .into_iter()
+        .next()
+        .ok_or(VmError::NoRepairFound)?;
+
+    repair::try_patch(&vm, patch)?
+        .map(|r| r.acc)
+        .ok_or(VmError::NoRepairFound)
 }
 
 #[test]
 pub fn test_part1() {
-    assert_eq!(
-        solve_part1(
-            "nop +0
-acc +1
-jmp +4
-acc +3
-jmp -3
-acc -99
-acc +1
-jmp -4
-acc +6"
-        ),
-        Ok(5),
-    );
+    assert_eq!(solve_part1(SAMPLE_PROGRAM), Ok(5),);
 }
 
 #[test]
 pub fn test_part2() {
-    assert_eq!(
-        solve_part2(
-            "nop +0
-acc +1
-jmp +4
-acc +3
-jmp -3
-acc -99
-acc +1
-jmp -4
-acc +6
-"
-        ),
-        Ok(8),
-    )
+    assert_eq!(solve_part2(SAMPLE_PROGRAM), Ok(8),)
 }
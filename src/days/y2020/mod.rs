@@ -10,12 +10,8 @@ pub mod day17;
 pub mod day18;
 pub mod day19;
 pub mod day2;
-pub mod day20;
-pub mod day21;
-pub mod day22;
 pub mod day23;
 pub mod day24;
-pub mod day25;
 pub mod day3;
 pub mod day4;
 pub mod day5;
@@ -23,3 +19,60 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+
+use std::convert::TryFrom;
+
+use aoc_2020::vm::Vm;
+
+use crate::{
+    solution::{DaySolution, Registry},
+    utils::grid::Grid,
+};
+
+pub fn register(registry: &mut Registry) {
+    registry.add(2020, 1, day1::solve_part1, day1::solve_part2);
+    registry.add(2020, 2, day2::solve_part1, day2::solve_part2);
+    registry.register(
+        DaySolution::new(2020, 3, day3::solve_part1, day3::solve_part2)
+            .with_parser(|input| input.parse::<Grid<char>>()),
+    );
+    registry.register(
+        DaySolution::new(2020, 4, day4::solve_part1, day4::solve_part2)
+            .with_parser(day4::generate_input),
+    );
+    registry.add(2020, 5, day5::solve_part1, day5::solve_part2);
+    registry.add(2020, 6, day6::solve_part1, day6::solve_part2);
+    registry.register(
+        DaySolution::new(2020, 7, day7::solve_part1, day7::solve_part2)
+            .with_parser(day7::parse_rules),
+    );
+    registry.register(
+        DaySolution::new(2020, 8, day8::solve_part1, day8::solve_part2)
+            .with_parser(|input| Vm::try_from(input)),
+    );
+    registry.add(2020, 9, day9::solve_part1, day9::solve_part2);
+    registry.add(2020, 10, day10::solve_part1, day10::solve_part2);
+    registry.register(
+        DaySolution::new(2020, 11, day11::solve_part1, day11::solve_part2)
+            .with_parser(day11::parse),
+    );
+    registry.add(2020, 12, day12::solve_part1, day12::solve_part2);
+    registry.register(
+        DaySolution::new(2020, 13, day13::solve_part1, day13::solve_part2)
+            .with_parser(day13::generate_input),
+    );
+    registry.add(2020, 14, day14::solve_part1, day14::solve_part2);
+    registry.add(2020, 15, day15::solve_part1, day15::solve_part2);
+    registry.add(2020, 16, day16::solve_part1, day16::solve_part2);
+    registry.add(2020, 17, day17::solve_part1, day17::solve_part2);
+    registry.add(2020, 18, day18::solve_part1, day18::solve_part2);
+    registry.add(2020, 19, day19::solve_part1, day19::solve_part2);
+    registry.register(
+        DaySolution::new(2020, 23, day23::solve_part1, day23::solve_part2)
+            .with_parser(day23::generate_input),
+    );
+    registry.register(
+        DaySolution::new(2020, 24, day24::solve_part1, day24::solve_part2)
+            .with_parser(day24::black_tiles),
+    );
+}
@@ -0,0 +1,384 @@
+use std::{
+    collections::BTreeSet,
+    convert::TryFrom,
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use aoc_2020::vm::{instruction::Instruction, Vm, VmError, VmState};
+use thiserror::Error;
+
+/// Number of steps after which `continue` gives control back, in case the program loops
+pub const CONTINUE_LIMIT: usize = 1_000_000;
+
+const HELP: &str = "step [N]            execute N instructions, 1 by default
+continue            run until a breakpoint, a watchpoint or the end of the program
+break <ip>          stop before executing the instruction at <ip>
+delete <ip>         remove the breakpoint at <ip>
+watch [value]       stop when acc changes, or when it reaches <value>
+unwatch             remove the watchpoint
+patch <idx> <instr> replace the instruction at <idx>, e.g. `patch 7 nop -4`
+reset               restore the original program and clear the machine state
+info                show ip, lp, acc and registers
+list                show the program listing
+diff                show the instructions patched since the program was loaded
+help                show this message
+quit                leave the debugger";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DebuggerError {
+    #[error("unknown command `{0}`, try `help`")]
+    UnknownCommand(String),
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("couldn't read program: {0}")]
+    IoError(String),
+    #[error(transparent)]
+    VmError(#[from] VmError),
+}
+
+impl From<io::Error> for DebuggerError {
+    fn from(e: io::Error) -> Self {
+        DebuggerError::IoError(e.to_string())
+    }
+}
+
+/// Condition on the accumulator that interrupts execution
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Watch {
+    Changed,
+    Equals(isize),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Break(usize),
+    Delete(usize),
+    Watch(Watch),
+    Unwatch,
+    Patch(usize, Instruction),
+    Reset,
+    Info,
+    List,
+    Diff,
+    Help,
+    Quit,
+}
+
+fn argument<T: FromStr>(argument: Option<&str>, name: &str) -> Result<T, DebuggerError> {
+    argument
+        .and_then(|a| a.parse().ok())
+        .ok_or_else(|| DebuggerError::InvalidArgument(format!("expected {}", name)))
+}
+
+impl FromStr for Command {
+    type Err = DebuggerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut words = s.splitn(2, char::is_whitespace);
+        let command = words.next().unwrap_or_default();
+        let rest = words.next().map(str::trim);
+
+        match command {
+            "s" | "step" => match rest {
+                Some(_) => Ok(Command::Step(argument(rest, "a number of steps")?)),
+                None => Ok(Command::Step(1)),
+            },
+            "c" | "continue" => Ok(Command::Continue),
+            "b" | "break" => Ok(Command::Break(argument(rest, "an address")?)),
+            "d" | "delete" => Ok(Command::Delete(argument(rest, "an address")?)),
+            "w" | "watch" => match rest {
+                Some(_) => Ok(Command::Watch(Watch::Equals(argument(rest, "a value")?))),
+                None => Ok(Command::Watch(Watch::Changed)),
+            },
+            "unwatch" => Ok(Command::Unwatch),
+            "p" | "patch" => {
+                let mut words = rest.unwrap_or_default().splitn(2, char::is_whitespace);
+                let index = argument(words.next(), "an address")?;
+                let instruction = Instruction::try_from(words.next().unwrap_or_default().trim())?;
+                Ok(Command::Patch(index, instruction))
+            }
+            "r" | "reset" => Ok(Command::Reset),
+            "i" | "info" => Ok(Command::Info),
+            "l" | "list" => Ok(Command::List),
+            "diff" => Ok(Command::Diff),
+            "h" | "help" => Ok(Command::Help),
+            "q" | "quit" => Ok(Command::Quit),
+            _ => Err(DebuggerError::UnknownCommand(s.to_owned())),
+        }
+    }
+}
+
+pub struct Debugger {
+    vm: Vm,
+    breakpoints: BTreeSet<usize>,
+    watch: Option<Watch>,
+}
+
+impl Debugger {
+    pub fn new(vm: Vm) -> Debugger {
+        Debugger {
+            vm,
+            breakpoints: BTreeSet::new(),
+            watch: None,
+        }
+    }
+
+    /// Instruction about to be executed
+    fn position(&self) -> String {
+        match self.vm.instructions.get(self.vm.ip) {
+            Some(instruction) => format!("=> {:04}  {}", self.vm.ip, instruction),
+            None => format!("=> {:04}  program terminated", self.vm.ip),
+        }
+    }
+
+    /// Executes a single instruction, returning the reason to stop if any
+    fn step(&mut self) -> Result<Option<String>, DebuggerError> {
+        let acc = self.vm.acc;
+        let state = self.vm.step()?;
+
+        if state == VmState::Terminated {
+            return Ok(Some(format!("Program terminated, acc = {}", self.vm.acc)));
+        }
+
+        if state == VmState::AwaitingInput {
+            return Ok(Some(format!("Waiting for input at {}", self.vm.ip)));
+        }
+
+        match self.watch {
+            Some(Watch::Changed) if self.vm.acc != acc => {
+                return Ok(Some(format!("Watchpoint: acc {} -> {}", acc, self.vm.acc)))
+            }
+            Some(Watch::Equals(value)) if self.vm.acc == value && acc != value => {
+                return Ok(Some(format!("Watchpoint: acc = {}", value)))
+            }
+            _ => {}
+        }
+
+        if self.breakpoints.contains(&self.vm.ip) {
+            return Ok(Some(format!("Breakpoint at {}", self.vm.ip)));
+        }
+
+        Ok(None)
+    }
+
+    /// Runs `command`, returning the text to display
+    pub fn execute(&mut self, command: Command) -> Result<String, DebuggerError> {
+        let vm = &mut self.vm;
+
+        match command {
+            Command::Step(steps) => {
+                let mut output = vec![];
+                for _ in 0..steps {
+                    let ip = self.vm.ip;
+                    let instruction = self.vm.instructions.get(ip).copied();
+                    let stop = self.step()?;
+
+                    if let Some(instruction) = instruction {
+                        output.push(format!("   {:04}  {}", ip, instruction));
+                    }
+                    if let Some(reason) = stop {
+                        output.push(reason);
+                        break;
+                    }
+                }
+
+                output.push(self.position());
+                Ok(output.join("\n"))
+            }
+            Command::Continue => {
+                for _ in 0..CONTINUE_LIMIT {
+                    if let Some(reason) = self.step()? {
+                        return Ok(format!("{}\n{}", reason, self.position()));
+                    }
+                }
+
+                Ok(format!(
+                    "Stopped after {} steps\n{}",
+                    CONTINUE_LIMIT,
+                    self.position()
+                ))
+            }
+            Command::Break(ip) => {
+                if ip >= vm.instructions.len() {
+                    return Err(DebuggerError::InvalidArgument(format!(
+                        "no instruction at {}",
+                        ip
+                    )));
+                }
+
+                self.breakpoints.insert(ip);
+                Ok(format!("Breakpoint set at {}", ip))
+            }
+            Command::Delete(ip) => {
+                if self.breakpoints.remove(&ip) {
+                    Ok(format!("Breakpoint at {} removed", ip))
+                } else {
+                    Err(DebuggerError::InvalidArgument(format!(
+                        "no breakpoint at {}",
+                        ip
+                    )))
+                }
+            }
+            Command::Watch(watch) => {
+                self.watch = Some(watch);
+                Ok("Watchpoint set on acc".to_owned())
+            }
+            Command::Unwatch => {
+                self.watch = None;
+                Ok("Watchpoint removed".to_owned())
+            }
+            Command::Patch(index, instruction) => {
+                if index >= vm.instructions.len() {
+                    return Err(DebuggerError::InvalidArgument(format!(
+                        "no instruction at {}",
+                        index
+                    )));
+                }
+
+                let previous = vm.instructions[index];
+                vm.patch_instruction(index, instruction);
+                Ok(format!(
+                    "Patched {}: {} -> {}",
+                    index, previous, instruction
+                ))
+            }
+            Command::Reset => {
+                vm.reset();
+                Ok(self.position())
+            }
+            Command::Info => {
+                let mut info = vec![
+                    format!("ip  = {}", vm.ip),
+                    match vm.lp {
+                        Some(lp) => format!("lp  = {}", lp),
+                        None => "lp  = none".to_owned(),
+                    },
+                    format!("acc = {}", vm.acc),
+                ];
+                info.extend(
+                    vm.registers
+                        .iter()
+                        .map(|(r, value)| format!("{}   = {}", r, value)),
+                );
+
+                Ok(info.join("\n"))
+            }
+            Command::List => Ok(vm.listing().to_string().trim_end().to_owned()),
+            Command::Diff => Ok(vm
+                .changes()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Command::Help => Ok(HELP.to_owned()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+}
+
+/// Loads the program stored in `path`, then reads commands from the standard input
+pub fn repl(path: &Path) -> Result<(), DebuggerError> {
+    let program = fs::read_to_string(path)?;
+    let mut debugger = Debugger::new(Vm::try_from(program.as_str())?);
+
+    println!("{}", debugger.position());
+    let stdin = io::stdin();
+    loop {
+        print!("(vm) ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match line.parse() {
+            Ok(Command::Quit) => return Ok(()),
+            Ok(command) => match debugger.execute(command) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("{}", e),
+            },
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoc_2020::vm::SAMPLE_PROGRAM;
+
+    fn run(debugger: &mut Debugger, command: &str) -> String {
+        debugger.execute(command.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    pub fn test_commands() {
+        assert_eq!("step".parse(), Ok(Command::Step(1)));
+        assert_eq!("s 3".parse(), Ok(Command::Step(3)));
+        assert_eq!("watch -2".parse(), Ok(Command::Watch(Watch::Equals(-2))));
+        assert_eq!(
+            "patch 7 nop -4".parse(),
+            Ok(Command::Patch(7, Instruction::try_from("nop -4").unwrap()))
+        );
+        assert_eq!(
+            "break".parse::<Command>(),
+            Err(DebuggerError::InvalidArgument(
+                "expected an address".to_owned()
+            ))
+        );
+        assert_eq!(
+            "jump 3".parse::<Command>(),
+            Err(DebuggerError::UnknownCommand("jump 3".to_owned()))
+        );
+    }
+
+    #[test]
+    pub fn test_session() {
+        let mut debugger = Debugger::new(Vm::try_from(SAMPLE_PROGRAM).unwrap());
+
+        assert_eq!(
+            run(&mut debugger, "step 2"),
+            "   0000  nop +0\n   0001  acc +1\n=> 0002  jmp +4"
+        );
+        assert_eq!(run(&mut debugger, "break 3"), "Breakpoint set at 3");
+        assert_eq!(
+            run(&mut debugger, "continue"),
+            "Breakpoint at 3\n=> 0003  acc +3"
+        );
+        assert_eq!(run(&mut debugger, "info"), "ip  = 3\nlp  = 7\nacc = 2");
+
+        run(&mut debugger, "watch 5");
+        assert_eq!(
+            run(&mut debugger, "continue"),
+            "Watchpoint: acc = 5\n=> 0004  jmp -3"
+        );
+
+        run(&mut debugger, "reset");
+        run(&mut debugger, "delete 3");
+        run(&mut debugger, "unwatch");
+        assert_eq!(
+            run(&mut debugger, "patch 7 nop -4"),
+            "Patched 7: jmp -4 -> nop -4"
+        );
+        assert_eq!(run(&mut debugger, "diff"), "-0007  jmp -4\n+0007  nop -4");
+        assert_eq!(
+            run(&mut debugger, "continue"),
+            "Program terminated, acc = 8\n=> 0009  program terminated"
+        );
+        assert_eq!(
+            debugger.execute(Command::Step(1)),
+            Err(DebuggerError::VmError(VmError::MachineTerminated))
+        );
+    }
+}
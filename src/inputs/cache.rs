@@ -0,0 +1,209 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use super::{InputError, InputFetcher};
+
+/// Name of the checksum manifest, stored at the root of the cache directory
+const MANIFEST: &str = "checksums.txt";
+
+/// Bodies returned by adventofcode.com instead of an input
+const ERROR_BODIES: [&str; 3] = [
+    "Please don't repeatedly request this endpoint",
+    "Puzzle inputs differ by user",
+    "404 Not Found",
+];
+
+/// Integrity of a cached input, according to the checksum manifest
+#[derive(Debug, PartialEq)]
+pub enum Integrity {
+    /// Input matches the checksum recorded when it was downloaded
+    Verified,
+    /// Input has been edited since it was downloaded
+    Modified,
+    /// Input isn't listed in the manifest
+    Untracked,
+}
+
+/// Offline store of inputs, laid out as `{dir}/{year}/day{day}.txt`
+pub struct InputCache {
+    dir: PathBuf,
+}
+
+impl InputCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> InputCache {
+        InputCache { dir: dir.into() }
+    }
+
+    pub fn path(&self, year: u32, day: u32) -> PathBuf {
+        self.dir
+            .join(year.to_string())
+            .join(format!("day{}.txt", day))
+    }
+
+    /// Validates `input`, then atomically writes it and records its checksum.
+    /// Error pages are rejected, so they never end up being read as inputs.
+    pub fn store(&self, year: u32, day: u32, input: &str) -> Result<(), InputError> {
+        validate(input)?;
+
+        write_atomically(&self.path(year, day), input)?;
+
+        let mut manifest = self.read_manifest()?;
+        manifest.insert((year, day), checksum(input));
+        self.write_manifest(&manifest)
+    }
+
+    pub fn verify(&self, year: u32, day: u32) -> Result<Integrity, InputError> {
+        let input = self.fetch(year, day)?;
+
+        Ok(match self.read_manifest()?.get(&(year, day)) {
+            Some(&sum) if sum == checksum(&input) => Integrity::Verified,
+            Some(_) => Integrity::Modified,
+            None => Integrity::Untracked,
+        })
+    }
+
+    fn read_manifest(&self) -> Result<BTreeMap<(u32, u32), u64>, InputError> {
+        let content = match fs::read_to_string(self.dir.join(MANIFEST)) {
+            Ok(s) => s,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Each line reads `{year} {day} {checksum}`
+        Ok(content
+            .lines()
+            .flat_map(|l| {
+                let mut fields = l.split_whitespace();
+                Some((
+                    (fields.next()?.parse().ok()?, fields.next()?.parse().ok()?),
+                    u64::from_str_radix(fields.next()?, 16).ok()?,
+                ))
+            })
+            .collect())
+    }
+
+    fn write_manifest(&self, manifest: &BTreeMap<(u32, u32), u64>) -> Result<(), InputError> {
+        let content: String = manifest
+            .iter()
+            .map(|((year, day), sum)| format!("{} {} {:016x}\n", year, day, sum))
+            .collect();
+
+        write_atomically(&self.dir.join(MANIFEST), &content)
+    }
+}
+
+impl InputFetcher for InputCache {
+    fn fetch(&self, year: u32, day: u32) -> Result<String, InputError> {
+        match fs::read_to_string(self.path(year, day)) {
+            Ok(s) => Ok(s),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(InputError::NotCached(year, day)),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn validate(input: &str) -> Result<(), InputError> {
+    if input.trim().is_empty() {
+        return Err(InputError::InvalidInput("empty body".to_owned()));
+    }
+
+    match ERROR_BODIES.iter().find(|&&e| input.contains(e)) {
+        Some(_) => Err(InputError::InvalidInput(
+            input.lines().next().unwrap_or_default().to_owned(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Writes to a temporary file next to `path`, then renames it into place,
+/// so an interrupted write never leaves a truncated file behind.
+fn write_atomically(path: &Path, content: &str) -> Result<(), InputError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+
+    fs::write(&tmp, content)?;
+    if let Err(e) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// 64-bit FNV-1a hash, stable across toolchains unlike `DefaultHasher`
+fn checksum(input: &str) -> u64 {
+    input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(name: &str) -> (PathBuf, InputCache) {
+        let dir =
+            std::env::temp_dir().join(format!("aoc-input-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        (dir.clone(), InputCache::new(&dir))
+    }
+
+    #[test]
+    pub fn test_store_and_fetch() {
+        let (dir, cache) = cache("store");
+
+        assert_eq!(cache.fetch(2020, 1), Err(InputError::NotCached(2020, 1)));
+
+        cache.store(2020, 1, "1721\n979\n").unwrap();
+        assert_eq!(cache.fetch(2020, 1), Ok("1721\n979\n".to_string()));
+        assert_eq!(cache.path(2020, 1), dir.join("2020").join("day1.txt"));
+
+        // Storing again replaces the input instead of appending to it
+        cache.store(2020, 1, "1721\n979\n").unwrap();
+        assert_eq!(cache.fetch(2020, 1), Ok("1721\n979\n".to_string()));
+        assert_eq!(fs::read_dir(dir.join("2020")).unwrap().count(), 1);
+    }
+
+    #[test]
+    pub fn test_error_bodies_are_not_cached() {
+        let (_, cache) = cache("error-bodies");
+
+        assert_eq!(
+            cache.store(
+                2020,
+                25,
+                "Please don't repeatedly request this endpoint before it unlocks! The calendar countdown is synchronized with the server time; the link will be enabled on the calendar the instant this puzzle becomes available.\n"
+            ),
+            Err(InputError::InvalidInput(
+                "Please don't repeatedly request this endpoint before it unlocks! The calendar countdown is synchronized with the server time; the link will be enabled on the calendar the instant this puzzle becomes available.".to_string()
+            ))
+        );
+        assert!(cache.store(2020, 25, "\n").is_err());
+        assert_eq!(cache.fetch(2020, 25), Err(InputError::NotCached(2020, 25)));
+    }
+
+    #[test]
+    pub fn test_verify() {
+        let (_, cache) = cache("verify");
+
+        cache.store(2020, 1, "1721\n979\n").unwrap();
+        cache.store(2020, 2, "1-3 a: abcde\n").unwrap();
+        assert_eq!(cache.verify(2020, 1), Ok(Integrity::Verified));
+        assert_eq!(cache.verify(2020, 2), Ok(Integrity::Verified));
+
+        fs::write(cache.path(2020, 1), "1721\n979\n366\n").unwrap();
+        assert_eq!(cache.verify(2020, 1), Ok(Integrity::Modified));
+        assert_eq!(cache.verify(2020, 2), Ok(Integrity::Verified));
+
+        fs::write(cache.path(2020, 3), "..##.......\n").unwrap();
+        assert_eq!(cache.verify(2020, 3), Ok(Integrity::Untracked));
+    }
+}
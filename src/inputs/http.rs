@@ -0,0 +1,175 @@
+use std::sync::Mutex;
+
+use curl::easy::Easy;
+
+use super::{InputError, InputFetcher};
+use crate::credentials::{CredentialProvider, Session};
+
+pub const AOC_URL: &str = "https://adventofcode.com";
+const USER_AGENT: &str = "github.com/tbarusseau/aoc-2020";
+
+impl From<curl::Error> for InputError {
+    fn from(e: curl::Error) -> Self {
+        InputError::RequestError(e.to_string())
+    }
+}
+
+/// Downloads inputs from adventofcode.com
+pub struct HttpFetcher {
+    base_url: String,
+    credentials: CredentialProvider,
+    client: Client,
+}
+
+impl HttpFetcher {
+    pub fn new(credentials: CredentialProvider) -> HttpFetcher {
+        HttpFetcher {
+            base_url: AOC_URL.to_owned(),
+            credentials,
+            client: Client::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(mut self, base_url: &str) -> HttpFetcher {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+/// HTTP handle used for every request sent to adventofcode.com, so that connections get reused
+pub struct Client {
+    easy: Mutex<Easy>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client {
+            easy: Mutex::new(Easy::new()),
+        }
+    }
+}
+
+impl Client {
+    /// Sends a request with the session cookie, returning the page's body. `fields` are posted
+    /// if given. `year` and `day` are only used to report a locked puzzle.
+    pub fn request(
+        &self,
+        url: &str,
+        session: &Session,
+        fields: Option<&str>,
+        year: u32,
+        day: u32,
+    ) -> Result<String, InputError> {
+        let mut easy = self.easy.lock().unwrap();
+        // Only the connections are kept, not the options of the previous request
+        easy.reset();
+        easy.url(url)?;
+        easy.useragent(USER_AGENT)?;
+        easy.cookie(&session.cookie())?;
+        if let Some(fields) = fields {
+            easy.post_fields_copy(fields.as_bytes())?;
+        }
+
+        let mut body = Vec::new();
+        {
+            let mut transfer = easy.transfer();
+            transfer.write_function(|data| {
+                body.extend_from_slice(data);
+                Ok(data.len())
+            })?;
+            transfer.perform()?;
+        }
+
+        match easy.response_code()? {
+            200 => Ok(String::from_utf8_lossy(&body).into_owned()),
+            400 => Err(InputError::BadSession),
+            404 => Err(InputError::NotUnlocked(year, day)),
+            429 | 503 => Err(InputError::RateLimited),
+            code => Err(InputError::UnexpectedStatus(code)),
+        }
+    }
+
+    /// Percent-encodes `value` for a form field
+    pub fn url_encode(&self, value: &str) -> String {
+        self.easy.lock().unwrap().url_encode(value.as_bytes())
+    }
+}
+
+impl InputFetcher for HttpFetcher {
+    fn fetch(&self, year: u32, day: u32) -> Result<String, InputError> {
+        // Fail before sending anything if there is no token to send
+        let session = self.credentials.session()?;
+        let url = format!("{}/{}/day/{}/input", self.base_url, year, day);
+
+        self.client.request(&url, &session, None, year, day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs::stub;
+
+    fn fetcher(url: &str) -> HttpFetcher {
+        HttpFetcher::new(CredentialProvider::with_token("abc123")).with_base_url(url)
+    }
+
+    #[test]
+    pub fn test_fetch() {
+        let (url, server) = stub::serve(vec![(200, "1721\n979\n")]);
+
+        assert_eq!(fetcher(&url).fetch(2020, 1), Ok("1721\n979\n".to_string()));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/2020/day/1/input");
+        assert_eq!(requests[0].header("Cookie"), Some("session=abc123"));
+    }
+
+    #[test]
+    pub fn test_client_reuse() {
+        let (url, server) = stub::serve(vec![(200, "posted"), (200, "fetched")]);
+        let client = Client::default();
+        let session = Session::new("abc123");
+
+        let page = |fields| client.request(&url, &session, fields, 2020, 1);
+        assert_eq!(page(Some("level=1")), Ok("posted".to_string()));
+        assert_eq!(page(None), Ok("fetched".to_string()));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].header("Cookie"), Some("session=abc123"));
+    }
+
+    #[test]
+    pub fn test_fetch_errors() {
+        let (url, server) = stub::serve(vec![
+            (
+                404,
+                "Please don't repeatedly request this endpoint before it unlocks!",
+            ),
+            (
+                400,
+                "Puzzle inputs differ by user.  Please log in to get your puzzle input.",
+            ),
+            (429, ""),
+            (500, ""),
+        ]);
+
+        let fetcher = fetcher(&url);
+        assert_eq!(
+            fetcher.fetch(2020, 25),
+            Err(InputError::NotUnlocked(2020, 25))
+        );
+        assert_eq!(fetcher.fetch(2020, 1), Err(InputError::BadSession));
+        assert_eq!(fetcher.fetch(2020, 1), Err(InputError::RateLimited));
+        assert_eq!(
+            fetcher.fetch(2020, 1),
+            Err(InputError::UnexpectedStatus(500))
+        );
+
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use super::{InputError, InputFetcher};
+
+/// Test double returning canned responses, and `NotUnlocked` for anything else
+#[derive(Default)]
+pub struct MockFetcher {
+    responses: HashMap<(u32, u32), Result<String, InputError>>,
+}
+
+impl MockFetcher {
+    pub fn with(mut self, year: u32, day: u32, response: Result<&str, InputError>) -> Self {
+        self.responses
+            .insert((year, day), response.map(str::to_owned));
+        self
+    }
+}
+
+impl InputFetcher for MockFetcher {
+    fn fetch(&self, year: u32, day: u32) -> Result<String, InputError> {
+        self.responses
+            .get(&(year, day))
+            .cloned()
+            .unwrap_or(Err(InputError::NotUnlocked(year, day)))
+    }
+}
@@ -0,0 +1,121 @@
+pub mod cache;
+pub mod http;
+#[cfg(test)]
+pub mod mock;
+#[cfg(test)]
+pub mod stub;
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::credentials::{CredentialError, CredentialProvider};
+use cache::{InputCache, Integrity};
+use http::HttpFetcher;
+
+/// Directory in which downloaded inputs are stored
+pub const INPUT_DIR: &str = "./input";
+
+#[derive(Debug, Error, PartialEq, Clone)]
+pub enum InputError {
+    #[error("puzzle {0} day {1} isn't unlocked yet")]
+    NotUnlocked(u32, u32),
+    #[error("session token was rejected, it's probably invalid or expired")]
+    BadSession,
+    #[error("rate limited by adventofcode.com, try again later")]
+    RateLimited,
+    #[error("unexpected HTTP status: {0}")]
+    UnexpectedStatus(u32),
+    #[error("HTTP request failed: {0}")]
+    RequestError(String),
+    #[error("input for {0} day {1} isn't available offline")]
+    NotCached(u32, u32),
+    #[error("downloaded input looks like an error page: {0}")]
+    InvalidInput(String),
+    #[error("credentials error: {0}")]
+    CredentialError(#[from] CredentialError),
+    #[error("i/o error: {0}")]
+    IoError(String),
+}
+
+impl From<std::io::Error> for InputError {
+    fn from(e: std::io::Error) -> Self {
+        InputError::IoError(e.to_string())
+    }
+}
+
+/// Source of puzzle inputs
+pub trait InputFetcher {
+    fn fetch(&self, year: u32, day: u32) -> Result<String, InputError>;
+}
+
+/// Reads the input from `cache`, downloading it from `remote` first if needed
+pub fn fetch_cached<F: InputFetcher>(
+    cache: &InputCache,
+    remote: &F,
+    year: u32,
+    day: u32,
+) -> Result<String, InputError> {
+    match cache.fetch(year, day) {
+        Err(InputError::NotCached(_, _)) => {
+            println!("Downloading input for {} day {}...", year, day);
+            let input = remote.fetch(year, day)?;
+            cache.store(year, day, &input)?;
+
+            Ok(input)
+        }
+        Ok(input) => {
+            if let Ok(Integrity::Modified) = cache.verify(year, day) {
+                println!(
+                    "Warning: input for {} day {} was edited since it was downloaded",
+                    year, day
+                );
+            }
+
+            Ok(input)
+        }
+        r => r,
+    }
+}
+
+/// Loads the input from `INPUT_DIR`, downloading it from adventofcode.com if needed.
+/// Credentials are only looked up when a download is required.
+pub fn load(year: u32, day: u32, session_file: &Option<PathBuf>) -> Result<String, InputError> {
+    let cache = InputCache::new(INPUT_DIR);
+    let remote = HttpFetcher::new(CredentialProvider::new(session_file.clone()));
+
+    fetch_cached(&cache, &remote, year, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mock::MockFetcher;
+
+    #[test]
+    pub fn test_fetch_cached() {
+        let dir = std::env::temp_dir().join(format!("aoc-fetch-cached-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = InputCache::new(&dir);
+        let remote = MockFetcher::default()
+            .with(2020, 1, Ok("1721\n979\n"))
+            .with(2020, 2, Err(InputError::RateLimited));
+
+        assert_eq!(
+            fetch_cached(&cache, &remote, 2020, 1),
+            Ok("1721\n979\n".to_string())
+        );
+        assert_eq!(cache.fetch(2020, 1), Ok("1721\n979\n".to_string()));
+
+        // Failed downloads are never cached
+        assert_eq!(
+            fetch_cached(&cache, &remote, 2020, 2),
+            Err(InputError::RateLimited)
+        );
+        assert_eq!(cache.fetch(2020, 2), Err(InputError::NotCached(2020, 2)));
+        assert_eq!(
+            fetch_cached(&cache, &remote, 2020, 3),
+            Err(InputError::NotUnlocked(2020, 3))
+        );
+    }
+}
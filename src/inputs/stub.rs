@@ -0,0 +1,94 @@
+//! Minimal HTTP server standing in for adventofcode.com in tests
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread::{self, JoinHandle},
+};
+
+/// Request received by the stub server
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Serves each `(status, body)` response once, in order, then stops.
+/// Returns the base URL of the server, and a handle yielding the received requests.
+pub fn serve(responses: Vec<(u32, &str)>) -> (String, JoinHandle<Vec<Request>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Couldn't bind stub server");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let responses: Vec<(u32, String)> = responses
+        .into_iter()
+        .map(|(status, body)| (status, body.to_owned()))
+        .collect();
+
+    let handle = thread::spawn(move || {
+        let mut requests = vec![];
+
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().expect("Couldn't accept connection");
+            let mut reader = BufReader::new(stream);
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let mut parts = line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_owned();
+            let path = parts.next().unwrap_or_default().to_owned();
+
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let mut header = line.splitn(2, ':');
+                if let (Some(k), Some(v)) = (header.next(), header.next()) {
+                    headers.push((k.trim().to_owned(), v.trim().to_owned()));
+                }
+            }
+
+            let length = headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Content-Length"))
+                .and_then(|(_, v)| v.parse().ok())
+                .unwrap_or(0);
+            let mut content = vec![0; length];
+            reader.read_exact(&mut content).unwrap();
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 {} STUB\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+
+            requests.push(Request {
+                method,
+                path,
+                headers,
+                body: String::from_utf8_lossy(&content).into_owned(),
+            });
+        }
+
+        requests
+    });
+
+    (url, handle)
+}
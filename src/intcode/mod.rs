@@ -0,0 +1,394 @@
+pub mod opcode;
+
+use opcode::{Mode, Opcode};
+use std::{collections::VecDeque, convert::TryFrom, num::ParseIntError};
+use thiserror::Error;
+
+/// Highest address a machine can write to, so that a bogus address can't exhaust the memory
+pub const MAX_ADDRESS: usize = (1 << 24) - 1;
+
+/// Consecutive reads of the default input, with no output in between, after which a machine is
+/// considered idle
+pub const IDLE_READS: usize = 2;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum IntcodeError {
+    #[error("numeral parsing error")]
+    ParsingError(#[from] ParseIntError),
+    #[error("invalid operation {0}")]
+    InvalidOperation(i64),
+    #[error("invalid parameter mode {0}")]
+    InvalidMode(i64),
+    #[error("parameter at {ip} must be an address")]
+    AddressExpected { ip: usize },
+    #[error("jump from {ip} to {target} leaves the program")]
+    JumpOutOfBounds { ip: usize, target: i64 },
+    #[error("access from {ip} to {address} leaves the memory")]
+    AddressOutOfBounds { ip: usize, address: i64 },
+    #[error("arithmetic overflow at {ip}")]
+    Overflow { ip: usize },
+    #[error("machine has already terminated")]
+    MachineTerminated,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum State {
+    Running,
+    /// The machine needs an input value to go on, it resumes once one is pushed
+    AwaitingInput,
+    Halted,
+}
+
+/// Intcode computer of the 2019 event
+#[derive(Debug, Clone)]
+pub struct Machine {
+    /// Memory, holding the program. Reads past its end return 0, writes grow it up to
+    /// `MAX_ADDRESS`.
+    pub memory: Vec<i64>,
+    /// Instruction pointer
+    pub ip: usize,
+    /// Base of the parameters in relative mode
+    pub relative_base: i64,
+    /// Values consumed by the input instruction, in order
+    pub input: VecDeque<i64>,
+    /// Values produced by the output instruction, in order
+    pub output: VecDeque<i64>,
+    /// Value read when the input queue is empty, instead of waiting for one (e.g. -1 for
+    /// non-blocking input)
+    pub default_input: Option<i64>,
+    state: State,
+    /// Consecutive reads of the default input since the last input or output value
+    idle_reads: usize,
+}
+
+impl TryFrom<&str> for Machine {
+    type Error = IntcodeError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let memory = value
+            .trim()
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        Ok(Machine::new(memory))
+    }
+}
+
+impl Machine {
+    pub fn new(memory: Vec<i64>) -> Machine {
+        Machine {
+            memory,
+            ip: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            default_input: None,
+            state: State::Running,
+            idle_reads: 0,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Whether the machine keeps reading its default input without producing anything, see
+    /// `IDLE_READS`
+    pub fn is_idle(&self) -> bool {
+        self.idle_reads >= IDLE_READS && self.input.is_empty()
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    pub fn pop_output(&mut self) -> Option<i64> {
+        self.output.pop_front()
+    }
+
+    /// Value stored at `address`
+    pub fn read(&self, address: usize) -> i64 {
+        self.memory.get(address).copied().unwrap_or(0)
+    }
+
+    pub fn write(&mut self, address: usize, value: i64) -> Result<(), IntcodeError> {
+        if address > MAX_ADDRESS {
+            return Err(IntcodeError::AddressOutOfBounds {
+                ip: self.ip,
+                address: i64::try_from(address).unwrap_or(i64::MAX),
+            });
+        }
+
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+
+        self.memory[address] = value;
+        Ok(())
+    }
+
+    /// Mode of the `n`-th parameter of the current instruction, starting at 1
+    fn mode(&self, n: u32) -> Result<Mode, IntcodeError> {
+        Mode::try_from(self.read(self.ip) / 10_i64.pow(n + 1) % 10)
+    }
+
+    /// Address designated by the `n`-th parameter of the current instruction
+    fn address(&self, n: u32) -> Result<usize, IntcodeError> {
+        let parameter = self.read(self.ip + n as usize);
+        let address = match self.mode(n)? {
+            Mode::Position => parameter,
+            Mode::Relative => self
+                .relative_base
+                .checked_add(parameter)
+                .ok_or(IntcodeError::Overflow { ip: self.ip })?,
+            Mode::Immediate => return Err(IntcodeError::AddressExpected { ip: self.ip }),
+        };
+
+        if address < 0 {
+            return Err(IntcodeError::AddressOutOfBounds {
+                ip: self.ip,
+                address,
+            });
+        }
+
+        Ok(address as usize)
+    }
+
+    /// Value of the `n`-th parameter of the current instruction
+    fn parameter(&self, n: u32) -> Result<i64, IntcodeError> {
+        match self.mode(n)? {
+            Mode::Immediate => Ok(self.read(self.ip + n as usize)),
+            _ => Ok(self.read(self.address(n)?)),
+        }
+    }
+
+    /// Executes a single instruction. Stepping while awaiting input is a no-op until a value is
+    /// pushed.
+    pub fn step(&mut self) -> Result<State, IntcodeError> {
+        if self.state == State::Halted {
+            return Err(IntcodeError::MachineTerminated);
+        }
+
+        let opcode = Opcode::try_from(self.read(self.ip) % 100)?;
+        let mut next = self.ip + 1 + opcode.parameters();
+
+        match opcode {
+            Opcode::Add | Opcode::Mul | Opcode::LessThan | Opcode::Equals => {
+                let (a, b) = (self.parameter(1)?, self.parameter(2)?);
+                let value = match opcode {
+                    Opcode::Add => a.checked_add(b),
+                    Opcode::Mul => a.checked_mul(b),
+                    Opcode::LessThan => Some((a < b) as i64),
+                    _ => Some((a == b) as i64),
+                }
+                .ok_or(IntcodeError::Overflow { ip: self.ip })?;
+                let address = self.address(3)?;
+                self.write(address, value)?;
+            }
+            Opcode::Input => {
+                let address = self.address(1)?;
+                match (self.input.pop_front(), self.default_input) {
+                    (Some(value), _) => {
+                        self.write(address, value)?;
+                        self.idle_reads = 0;
+                    }
+                    (None, Some(value)) => {
+                        self.write(address, value)?;
+                        self.idle_reads += 1;
+                    }
+                    (None, None) => {
+                        self.state = State::AwaitingInput;
+                        return Ok(self.state);
+                    }
+                }
+            }
+            Opcode::Output => {
+                let value = self.parameter(1)?;
+                self.output.push_back(value);
+                self.idle_reads = 0;
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let condition = self.parameter(1)? != 0;
+                if condition == (opcode == Opcode::JumpIfTrue) {
+                    let target = self.parameter(2)?;
+                    if target < 0 {
+                        return Err(IntcodeError::JumpOutOfBounds {
+                            ip: self.ip,
+                            target,
+                        });
+                    }
+
+                    next = target as usize;
+                }
+            }
+            Opcode::AdjustRelativeBase => {
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(self.parameter(1)?)
+                    .ok_or(IntcodeError::Overflow { ip: self.ip })?;
+            }
+            Opcode::Halt => {
+                self.state = State::Halted;
+                return Ok(self.state);
+            }
+        }
+
+        self.ip = next;
+        self.state = State::Running;
+        Ok(self.state)
+    }
+
+    /// Steps until the machine halts, needs an input value that hasn't been pushed yet, or
+    /// becomes idle when it has a default input
+    pub fn run(&mut self) -> Result<State, IntcodeError> {
+        loop {
+            match self.step()? {
+                State::Running if !self.is_idle() => {}
+                state => return Ok(state),
+            }
+        }
+    }
+
+    /// Pushes `input`, runs the machine and returns every value it output
+    pub fn run_with(&mut self, input: &[i64]) -> Result<Vec<i64>, IntcodeError> {
+        self.input.extend(input);
+        self.run()?;
+
+        Ok(self.output.drain(..).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_arithmetic() {
+        let mut machine = Machine::try_from("1,9,10,3,2,3,11,0,99,30,40,50").unwrap();
+        assert_eq!(machine.run(), Ok(State::Halted));
+        assert_eq!(machine.memory[0], 3500);
+        assert_eq!(machine.step(), Err(IntcodeError::MachineTerminated));
+
+        let mut machine = Machine::try_from("1101,100,-1,4,0").unwrap();
+        assert_eq!(machine.run(), Ok(State::Halted));
+        assert_eq!(machine.memory[4], 99);
+    }
+
+    #[test]
+    pub fn test_input_output() {
+        // Outputs 1 if the input is 8, 0 otherwise
+        let program = "3,9,8,9,10,9,4,9,99,-1,8";
+        assert_eq!(
+            Machine::try_from(program).unwrap().run_with(&[8]),
+            Ok(vec![1])
+        );
+        assert_eq!(
+            Machine::try_from(program).unwrap().run_with(&[7]),
+            Ok(vec![0])
+        );
+
+        // Outputs 0 if the input is 0, 1 otherwise
+        let mut machine = Machine::try_from("3,3,1105,-1,9,1101,0,0,12,4,12,99,1").unwrap();
+        assert_eq!(machine.run(), Ok(State::AwaitingInput));
+        assert_eq!(machine.step(), Ok(State::AwaitingInput));
+        machine.push_input(5);
+        assert_eq!(machine.run(), Ok(State::Halted));
+        assert_eq!(machine.pop_output(), Some(1));
+        assert_eq!(machine.pop_output(), None);
+    }
+
+    #[test]
+    pub fn test_default_input() {
+        // Echoes its input until it reads a negative value
+        let program = "3,20,1007,20,0,21,1005,21,14,4,20,1105,1,0,99";
+        let mut machine = Machine::try_from(program).unwrap();
+        machine.default_input = Some(-1);
+        machine.push_input(3);
+        assert_eq!(machine.run(), Ok(State::Halted));
+        assert_eq!(machine.output, vec![3]);
+
+        // Keeps polling its input, outputting nothing until it reads a non-negative value
+        let program = "3,20,1007,20,0,21,1005,21,0,4,20,1105,1,0";
+        let mut machine = Machine::try_from(program).unwrap();
+        machine.default_input = Some(-1);
+        assert_eq!(machine.run(), Ok(State::Running));
+        assert!(machine.is_idle());
+
+        machine.push_input(7);
+        assert!(!machine.is_idle());
+        assert_eq!(machine.run(), Ok(State::Running));
+        assert_eq!(machine.pop_output(), Some(7));
+        assert!(machine.is_idle());
+    }
+
+    #[test]
+    pub fn test_relative_mode() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let expected: Vec<i64> = quine.split(',').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(
+            Machine::try_from(quine).unwrap().run_with(&[]),
+            Ok(expected)
+        );
+
+        let mut machine = Machine::try_from("104,1125899906842624,99").unwrap();
+        assert_eq!(machine.run_with(&[]), Ok(vec![1125899906842624]));
+    }
+
+    #[test]
+    pub fn test_errors() {
+        assert!(matches!(
+            Machine::try_from("1,2,x"),
+            Err(IntcodeError::ParsingError(_))
+        ));
+        assert_eq!(
+            Machine::try_from("42").unwrap().run(),
+            Err(IntcodeError::InvalidOperation(42))
+        );
+        assert_eq!(
+            Machine::try_from("11101,1,1,1").unwrap().run(),
+            Err(IntcodeError::AddressExpected { ip: 0 })
+        );
+        assert_eq!(
+            Machine::try_from("301,0,0,0").unwrap().run(),
+            Err(IntcodeError::InvalidMode(3))
+        );
+    }
+
+    #[test]
+    pub fn test_limits() {
+        let program = format!("1102,{},2,5,99,0", i64::MAX);
+        assert_eq!(
+            Machine::try_from(program.as_str()).unwrap().run(),
+            Err(IntcodeError::Overflow { ip: 0 })
+        );
+
+        let program = format!("109,{},109,1,99", i64::MAX);
+        assert_eq!(
+            Machine::try_from(program.as_str()).unwrap().run(),
+            Err(IntcodeError::Overflow { ip: 2 })
+        );
+
+        let program = format!("1101,1,1,{},99", MAX_ADDRESS + 1);
+        assert_eq!(
+            Machine::try_from(program.as_str()).unwrap().run(),
+            Err(IntcodeError::AddressOutOfBounds {
+                ip: 0,
+                address: MAX_ADDRESS as i64 + 1
+            })
+        );
+
+        assert_eq!(
+            Machine::try_from("1,-1,0,0,99").unwrap().run(),
+            Err(IntcodeError::AddressOutOfBounds { ip: 0, address: -1 })
+        );
+        assert_eq!(
+            Machine::try_from("1105,1,-3,99").unwrap().run(),
+            Err(IntcodeError::JumpOutOfBounds { ip: 0, target: -3 })
+        );
+
+        let mut machine = Machine::try_from("1101,1,1,1000,99").unwrap();
+        assert_eq!(machine.run(), Ok(State::Halted));
+        assert_eq!(machine.read(1000), 2);
+    }
+}
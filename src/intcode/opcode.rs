@@ -0,0 +1,85 @@
+use std::convert::TryFrom;
+
+use super::IntcodeError;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Opcode {
+    /// Add the first two parameters, store the result at the third
+    Add,
+    /// Multiply the first two parameters, store the result at the third
+    Mul,
+    /// Store the next input value at the parameter
+    Input,
+    /// Output the parameter
+    Output,
+    /// Jump to the second parameter if the first one is non-zero
+    JumpIfTrue,
+    /// Jump to the second parameter if the first one is zero
+    JumpIfFalse,
+    /// Store 1 at the third parameter if the first one is less than the second, 0 otherwise
+    LessThan,
+    /// Store 1 at the third parameter if the first two are equal, 0 otherwise
+    Equals,
+    /// Add the parameter to the relative base
+    AdjustRelativeBase,
+    /// Stop the machine
+    Halt,
+}
+
+use Opcode::*;
+
+impl Opcode {
+    /// Number of parameters following the opcode
+    pub fn parameters(self) -> usize {
+        match self {
+            Halt => 0,
+            Input | Output | AdjustRelativeBase => 1,
+            JumpIfTrue | JumpIfFalse => 2,
+            Add | Mul | LessThan | Equals => 3,
+        }
+    }
+}
+
+impl TryFrom<i64> for Opcode {
+    type Error = IntcodeError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Add),
+            2 => Ok(Mul),
+            3 => Ok(Input),
+            4 => Ok(Output),
+            5 => Ok(JumpIfTrue),
+            6 => Ok(JumpIfFalse),
+            7 => Ok(LessThan),
+            8 => Ok(Equals),
+            9 => Ok(AdjustRelativeBase),
+            99 => Ok(Halt),
+            _ => Err(IntcodeError::InvalidOperation(value)),
+        }
+    }
+}
+
+/// How a parameter is interpreted
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Mode {
+    /// The parameter is the address of the value
+    Position,
+    /// The parameter is the value itself
+    Immediate,
+    /// The parameter is the address of the value, relative to the relative base
+    Relative,
+}
+
+impl TryFrom<i64> for Mode {
+    type Error = IntcodeError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Mode::Position),
+            1 => Ok(Mode::Immediate),
+            2 => Ok(Mode::Relative),
+            _ => Err(IntcodeError::InvalidMode(value)),
+        }
+    }
+}
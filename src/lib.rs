@@ -1 +1,3 @@
+pub mod intcode;
+pub mod network;
 pub mod vm;
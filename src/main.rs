@@ -1,36 +1,189 @@
 #![feature(destructuring_assignment)]
 
+mod answer;
+mod calendar;
+mod check;
+mod credentials;
 mod days;
-
-#[macro_use]
+mod debugger;
+mod inputs;
+mod runner;
+mod solution;
+mod submit;
+mod timing;
 mod utils;
 
-use days::*;
+use std::path::{Path, PathBuf};
 
-use chrono::{Datelike, Local};
+use calendar::DayRange;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "AoC 2020")]
 struct Opt {
+    /// Run every unlocked day of the year
     #[structopt(short, long)]
     all: bool,
+    /// Event to run, defaults to the latest one with solutions
+    #[structopt(short, long)]
+    year: Option<u32>,
+    /// Single day to run
+    #[structopt(
+        long,
+        conflicts_with_all = &["all", "days"],
+        parse(try_from_str = calendar::parse_day)
+    )]
+    day: Option<u32>,
+    /// Days or ranges of days to run, e.g. `-d 1-10 -d 14`.
+    /// Defaults to the latest unlocked day.
+    #[structopt(short, long, conflicts_with = "all")]
+    days: Option<Vec<DayRange>>,
+    /// Only run the given part
+    #[structopt(short, long, possible_values = &["1", "2"])]
+    part: Option<u8>,
+    /// Print how long each input and part took
+    #[structopt(short, long)]
+    time: bool,
+    /// Run each part N times and report min, median and max durations.
+    /// Parts then run one after the other, so that they don't slow each other down.
+    #[structopt(long, name = "N", conflicts_with = "jobs")]
+    bench: Option<usize>,
+    /// Compare answers with the ones recorded in `answers/{year}.toml`
     #[structopt(short, long)]
-    days: Option<Vec<u32>>,
+    check: bool,
+    /// Maximum number of parts running at the same time, defaults to the number of CPUs
+    #[structopt(short, long)]
+    jobs: Option<usize>,
+    /// Run parts one after the other, same as `--jobs 1`
+    #[structopt(short, long, conflicts_with = "jobs")]
+    sequential: bool,
+    /// File containing the adventofcode.com session token
+    #[structopt(long, parse(from_os_str))]
+    session_file: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Submit an answer to adventofcode.com, for the event given with `--year`
+    Submit {
+        #[structopt(parse(try_from_str = calendar::parse_day))]
+        day: u32,
+        #[structopt(possible_values = &["1", "2"])]
+        part: u8,
+        answer: String,
+    },
+    /// Load a program for the handheld console VM and debug it interactively
+    VmDebug {
+        #[structopt(parse(from_os_str))]
+        program: PathBuf,
+    },
 }
 
 #[async_std::main]
 async fn main() {
-    let mut opt = Opt::from_args();
-    let today = Local::now().day();
+    let opt = Opt::from_args();
+    let now = calendar::now();
+    let registry = days::registry();
+    let year = opt
+        .year
+        .or_else(|| registry.latest_year())
+        .unwrap_or_else(|| calendar::default_year(&now));
+
+    match opt.command {
+        Some(Command::Submit { day, part, answer }) => {
+            match submit::submit(year, day, part, &answer, &opt.session_file) {
+                Ok(outcome) => println!("Day {}, part {}: {}", day, part, outcome),
+                Err(e) => {
+                    eprintln!("Couldn't submit {}: {}", answer, e);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+        Some(Command::VmDebug { program }) => {
+            if let Err(e) = debugger::repl(&program) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+        None => {}
+    }
+
+    let unlocked = calendar::unlocked_days(year, &now);
+    let is_solved = |day: &u32| registry.get(year, *day).is_some();
+
+    let mut days: Vec<u32> = if opt.all {
+        unlocked.iter().cloned().filter(is_solved).collect()
+    } else if let Some(day) = opt.day {
+        vec![day]
+    } else if let Some(ranges) = opt.days {
+        ranges.iter().flat_map(DayRange::days).collect()
+    } else {
+        unlocked
+            .iter()
+            .rev()
+            .cloned()
+            .find(is_solved)
+            .into_iter()
+            .collect()
+    };
+    days.sort_unstable();
+    days.dedup();
+
+    if days.is_empty() {
+        eprintln!("No unlocked puzzle of {} has a solution", year);
+        std::process::exit(1);
+    }
+    if let Some(day) = days.iter().find(|d| !unlocked.contains(d)) {
+        eprintln!("Day {} of {} isn't unlocked yet", day, year);
+        std::process::exit(1);
+    }
+    if let Some(day) = days.iter().find(|d| !is_solved(d)) {
+        eprintln!("Day {} of {} has no solution", day, year);
+        std::process::exit(1);
+    }
+
+    let parts = match opt.part {
+        Some(part) => vec![part],
+        None => vec![1, 2],
+    };
 
-    aoc_import!(2020, 25);
+    let mut config = runner::RunConfig {
+        year,
+        days,
+        parts,
+        session_file: opt.session_file,
+        runs: opt.bench.unwrap_or(1),
+        jobs: match (opt.sequential || opt.bench.is_some(), opt.jobs) {
+            (true, _) => 1,
+            (false, Some(jobs)) => jobs,
+            (false, None) => rayon::current_num_threads(),
+        },
+        report: opt.time || opt.bench.is_some(),
+        expected: None,
+    };
 
-    if opt.all {
-        opt.days = Some((1..=today).collect());
-    } else if opt.days.is_none() {
-        opt.days = Some(vec![today]);
+    if opt.check {
+        match check::ExpectedAnswers::load(Path::new(check::ANSWERS_DIR), year) {
+            Ok(expected) => config.expected = Some(expected),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
     }
 
-    aoc_match!(opt, 2020, 25);
+    let results = runner::run(&registry, &config).await;
+
+    if results
+        .iter()
+        .any(|r| matches!(r.verdict, Some(check::Verdict::Fail(_))))
+    {
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,769 @@
+use std::{
+    convert::TryFrom,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+use async_std::task;
+use futures::{channel::mpsc, StreamExt};
+use thiserror::Error;
+
+use crate::{
+    intcode::{IntcodeError, Machine, State},
+    vm::{op::Op, Vm, VmError, VmState},
+};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum NetworkError<E: Error + 'static> {
+    #[error("machines must be allowed at least one instruction at a time")]
+    ZeroQuantum,
+    #[error("machine {index} failed: {error}")]
+    Node { index: usize, error: E },
+}
+
+/// Execution state of a `Node`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Status {
+    Running,
+    /// The node needs an input value to go on, it resumes once one is pushed
+    AwaitingInput,
+    Halted,
+}
+
+/// Machine that can be part of a `Network`, exchanging `i64` values with the other nodes
+pub trait Node: Send + 'static {
+    type Error: Error + Send + 'static;
+
+    /// Executes a single instruction
+    fn step(&mut self) -> Result<Status, Self::Error>;
+
+    fn status(&self) -> Status;
+
+    fn push_input(&mut self, value: i64);
+
+    /// Whether some input is waiting to be read
+    fn has_input(&self) -> bool;
+
+    /// Values output and not delivered yet, oldest first
+    fn output(&self) -> Vec<i64>;
+
+    /// Removes the `count` oldest output values
+    fn consume_output(&mut self, count: usize);
+
+    /// Whether the node can't go on until it receives some input
+    fn is_blocked(&self) -> bool {
+        match self.status() {
+            Status::Halted => true,
+            Status::AwaitingInput => !self.has_input(),
+            Status::Running => false,
+        }
+    }
+
+    /// Steps until the node halts or gets blocked, at most `quantum` times
+    fn run(&mut self, quantum: usize) -> Result<Status, Self::Error> {
+        let mut status = self.status();
+        for _ in 0..quantum {
+            if self.is_blocked() {
+                break;
+            }
+
+            status = self.step()?;
+            if status != Status::Running {
+                break;
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+impl Node for Machine {
+    type Error = IntcodeError;
+
+    fn step(&mut self) -> Result<Status, IntcodeError> {
+        Machine::step(self).map(Status::from)
+    }
+
+    fn status(&self) -> Status {
+        Status::from(self.state())
+    }
+
+    fn push_input(&mut self, value: i64) {
+        Machine::push_input(self, value);
+    }
+
+    fn has_input(&self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn output(&self) -> Vec<i64> {
+        self.output.iter().copied().collect()
+    }
+
+    fn consume_output(&mut self, count: usize) {
+        self.output.drain(..count);
+    }
+
+    /// Machines with a default input never wait for input, they are blocked once idle instead
+    fn is_blocked(&self) -> bool {
+        match self.state() {
+            State::Halted => true,
+            State::AwaitingInput => self.input.is_empty(),
+            State::Running => self.is_idle(),
+        }
+    }
+}
+
+impl From<State> for Status {
+    fn from(state: State) -> Self {
+        match state {
+            State::Running => Status::Running,
+            State::AwaitingInput => Status::AwaitingInput,
+            State::Halted => Status::Halted,
+        }
+    }
+}
+
+/// Virtual machines exchange values with `snd` and `rcv`
+impl Node for Vm {
+    type Error = VmError;
+
+    fn step(&mut self) -> Result<Status, VmError> {
+        Vm::step(self).map(|state| match state {
+            VmState::Running => Status::Running,
+            VmState::AwaitingInput => Status::AwaitingInput,
+            VmState::Terminated => Status::Halted,
+        })
+    }
+
+    fn status(&self) -> Status {
+        match self.instructions.get(self.ip) {
+            None => Status::Halted,
+            Some(instruction) if instruction.op == Op::Rcv && self.input.is_empty() => {
+                Status::AwaitingInput
+            }
+            Some(_) => Status::Running,
+        }
+    }
+
+    fn push_input(&mut self, value: i64) {
+        self.input.push_back(value as isize);
+    }
+
+    fn has_input(&self) -> bool {
+        !self.input.is_empty()
+    }
+
+    fn output(&self) -> Vec<i64> {
+        self.output.iter().map(|&value| value as i64).collect()
+    }
+
+    fn consume_output(&mut self, count: usize) {
+        self.output.drain(..count);
+    }
+}
+
+/// Values sent to an address that doesn't belong to the network
+#[derive(Debug, PartialEq, Clone)]
+pub struct Packet {
+    pub address: i64,
+    pub values: Vec<i64>,
+}
+
+/// How the outputs of a node are dispatched
+#[derive(Debug, Clone)]
+enum Routing {
+    /// Every output of a node goes to the input of a fixed node
+    Links(Vec<Option<usize>>),
+    /// Outputs are packets of `size` values, the first one being the index of the node to send
+    /// the other ones to
+    Addressed { size: usize },
+}
+
+/// Output of a node, split by receiver
+#[derive(Default)]
+struct Delivery {
+    /// Number of output values sent
+    consumed: usize,
+    /// Values for each receiving node
+    sent: Vec<(usize, Vec<i64>)>,
+    /// Packets sent outside the network
+    external: Vec<Packet>,
+}
+
+impl Routing {
+    /// Dispatches the pending `output` of node `from`, stopping at the first value for a node
+    /// that halted
+    fn route(&self, from: usize, output: &[i64], halted: &[bool]) -> Delivery {
+        let mut delivery = Delivery::default();
+        match self {
+            Routing::Links(links) => {
+                if let Some(to) = links[from] {
+                    if !output.is_empty() && !halted[to] {
+                        delivery.consumed = output.len();
+                        delivery.sent.push((to, output.to_vec()));
+                    }
+                }
+            }
+            Routing::Addressed { size } => {
+                for packet in output.chunks_exact(*size) {
+                    let (address, values) = (packet[0], packet[1..].to_vec());
+                    match usize::try_from(address) {
+                        Ok(to) if to < halted.len() => {
+                            if halted[to] {
+                                break;
+                            }
+                            delivery.sent.push((to, values));
+                        }
+                        _ => delivery.external.push(Packet { address, values }),
+                    }
+                    delivery.consumed += size;
+                }
+            }
+        }
+
+        delivery
+    }
+}
+
+/// State in which a network stopped
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    /// Every machine halted
+    Halted,
+    /// No machine can go on: the ones that didn't halt are waiting for input that no other machine
+    /// will send. Unless more input gets pushed from outside, the network is deadlocked.
+    Idle { waiting: Vec<usize> },
+}
+
+/// Machines whose outputs are sent to other machines' inputs, either through fixed links (e.g.
+/// amplifier chains or feedback loops) or as addressed packets.
+///
+/// Values sent to a machine that already halted stay in the output queue of the sender, while
+/// values a machine received but didn't read before halting stay in its input queue.
+pub struct Network<N> {
+    pub machines: Vec<N>,
+    /// Packets sent to addresses outside the network, in order
+    pub outbox: Vec<Packet>,
+    routing: Routing,
+}
+
+impl<N: Node> Network<N> {
+    pub fn new(machines: Vec<N>) -> Network<N> {
+        let links = vec![None; machines.len()];
+        Network {
+            machines,
+            outbox: vec![],
+            routing: Routing::Links(links),
+        }
+    }
+
+    /// Machines sending packets of `size` values, made of the index of the receiving machine
+    /// followed by the values it gets. Packets for other addresses end up in the outbox.
+    pub fn addressed(machines: Vec<N>, size: usize) -> Network<N> {
+        assert!(size > 0, "packets must hold at least an address");
+        Network {
+            machines,
+            outbox: vec![],
+            routing: Routing::Addressed { size },
+        }
+    }
+
+    /// Sends every output of machine `from` to the input of machine `to`
+    pub fn connect(&mut self, from: usize, to: usize) {
+        match &mut self.routing {
+            Routing::Links(links) => links[from] = Some(to),
+            Routing::Addressed { .. } => panic!("machines of an addressed network aren't linked"),
+        }
+    }
+
+    /// Sends the outputs of every machine to the next one, the last machine feeding the first
+    pub fn ring(machines: Vec<N>) -> Network<N> {
+        let mut network = Network::new(machines);
+        let len = network.machines.len();
+        for from in 0..len {
+            network.connect(from, (from + 1) % len);
+        }
+
+        network
+    }
+
+    fn outcome(&self) -> Outcome {
+        let waiting: Vec<usize> = (0..self.machines.len())
+            .filter(|&i| self.machines[i].status() != Status::Halted)
+            .collect();
+
+        if waiting.is_empty() {
+            Outcome::Halted
+        } else {
+            Outcome::Idle { waiting }
+        }
+    }
+
+    /// Moves the outputs of machine `from` to the inputs of the machines they are sent to
+    fn deliver(&mut self, from: usize) {
+        let output = self.machines[from].output();
+        if output.is_empty() {
+            return;
+        }
+
+        let halted: Vec<bool> = self
+            .machines
+            .iter()
+            .map(|m| m.status() == Status::Halted)
+            .collect();
+        let delivery = self.routing.route(from, &output, &halted);
+
+        self.machines[from].consume_output(delivery.consumed);
+        for (to, values) in delivery.sent {
+            for value in values {
+                self.machines[to].push_input(value);
+            }
+        }
+        self.outbox.extend(delivery.external);
+    }
+
+    /// Steps every machine in turn, at most `quantum` instructions at a time, until they all
+    /// halt or wait for input
+    pub fn run(&mut self, quantum: usize) -> Result<Outcome, NetworkError<N::Error>> {
+        if quantum == 0 {
+            return Err(NetworkError::ZeroQuantum);
+        }
+
+        loop {
+            for index in 0..self.machines.len() {
+                self.machines[index]
+                    .run(quantum)
+                    .map_err(|error| NetworkError::Node { index, error })?;
+                self.deliver(index);
+            }
+
+            if self.machines.iter().all(N::is_blocked) {
+                return Ok(self.outcome());
+            }
+        }
+    }
+
+    /// Runs every machine in its own task until they all halt or wait for input. Tasks yield
+    /// after at most `quantum` instructions, so that a busy machine doesn't hold its thread.
+    pub async fn run_async(&mut self, quantum: usize) -> Result<Outcome, NetworkError<N::Error>> {
+        if quantum == 0 {
+            return Err(NetworkError::ZeroQuantum);
+        }
+
+        let (senders, inboxes): (Vec<_>, Vec<_>) =
+            self.machines.iter().map(|_| mpsc::unbounded()).unzip();
+        let shared = Arc::new(Mutex::new(Shared {
+            senders,
+            halted: self
+                .machines
+                .iter()
+                .map(|m| m.status() == Status::Halted)
+                .collect(),
+            waiting: vec![false; self.machines.len()],
+            pending: 0,
+            outbox: vec![],
+            stopped: false,
+        }));
+
+        let routing = self.routing.clone();
+        let tasks: Vec<_> = self
+            .machines
+            .drain(..)
+            .zip(inboxes)
+            .enumerate()
+            .map(|(index, (machine, inbox))| {
+                task::spawn(run_task(
+                    index,
+                    machine,
+                    quantum,
+                    routing.clone(),
+                    shared.clone(),
+                    inbox,
+                ))
+            })
+            .collect();
+
+        let mut result = Ok(());
+        for (index, task) in tasks.into_iter().enumerate() {
+            match task.await {
+                Ok(machine) => self.machines.push(machine),
+                Err((machine, error)) => {
+                    self.machines.push(machine);
+                    result = result.and(Err(NetworkError::Node { index, error }));
+                }
+            }
+        }
+
+        self.outbox.append(&mut shared.lock().unwrap().outbox);
+        result.map(|_| self.outcome())
+    }
+}
+
+enum Message {
+    Values(Vec<i64>),
+    /// The network is idle or a machine failed, the receiving task gives up
+    Stop,
+}
+
+/// State shared by the tasks of `Network::run_async`
+struct Shared {
+    senders: Vec<mpsc::UnboundedSender<Message>>,
+    /// Machines that halted or failed, nothing gets sent to them anymore
+    halted: Vec<bool>,
+    /// Machines waiting for a message
+    waiting: Vec<bool>,
+    /// Values messages sent and not received yet
+    pending: usize,
+    outbox: Vec<Packet>,
+    stopped: bool,
+}
+
+impl Shared {
+    fn is_idle(&self) -> bool {
+        self.pending == 0 && (0..self.halted.len()).all(|i| self.halted[i] || self.waiting[i])
+    }
+
+    /// Sends the pending output of machine `from` to the tasks of its receivers
+    fn send<N: Node>(&mut self, from: usize, machine: &mut N, routing: &Routing) {
+        let output = machine.output();
+        if output.is_empty() {
+            return;
+        }
+
+        let delivery = routing.route(from, &output, &self.halted);
+        machine.consume_output(delivery.consumed);
+        for (to, values) in delivery.sent {
+            self.pending += 1;
+            // Receivers that haven't halted are still listening
+            let _ = self.senders[to].unbounded_send(Message::Values(values));
+        }
+        self.outbox.extend(delivery.external);
+    }
+
+    /// Moves the values left in `inbox` to the input of `machine`, which stops listening
+    fn close<N: Node>(
+        &mut self,
+        index: usize,
+        machine: &mut N,
+        inbox: &mut mpsc::UnboundedReceiver<Message>,
+    ) {
+        self.halted[index] = true;
+        inbox.close();
+        while let Ok(Some(message)) = inbox.try_next() {
+            if let Message::Values(values) = message {
+                self.pending -= 1;
+                for value in values {
+                    machine.push_input(value);
+                }
+            }
+        }
+    }
+
+    /// Hands a message from its inbox to machine `index`. Returns whether its task goes on.
+    fn receive<N: Node>(
+        &mut self,
+        index: usize,
+        machine: &mut N,
+        inbox: &mut mpsc::UnboundedReceiver<Message>,
+        message: Option<Message>,
+    ) -> bool {
+        match message {
+            Some(Message::Values(values)) => {
+                self.pending -= 1;
+                self.waiting[index] = false;
+                for value in values {
+                    machine.push_input(value);
+                }
+                true
+            }
+            Some(Message::Stop) | None => {
+                // Values received but not read stay in the input queue, as with `Network::run`
+                self.close(index, machine, inbox);
+                false
+            }
+        }
+    }
+
+    /// Tells every task to give up
+    fn stop(&mut self) {
+        if !self.stopped {
+            self.stopped = true;
+            for sender in &self.senders {
+                let _ = sender.unbounded_send(Message::Stop);
+            }
+        }
+    }
+}
+
+async fn run_task<N: Node>(
+    index: usize,
+    mut machine: N,
+    quantum: usize,
+    routing: Routing,
+    shared: Arc<Mutex<Shared>>,
+    mut inbox: mpsc::UnboundedReceiver<Message>,
+) -> Result<N, (N, N::Error)> {
+    loop {
+        let result = machine.run(quantum);
+        let blocked = machine.is_blocked();
+        {
+            let mut shared = shared.lock().unwrap();
+            match result {
+                Ok(status) => {
+                    shared.send(index, &mut machine, &routing);
+                    if status == Status::Halted {
+                        shared.close(index, &mut machine, &mut inbox);
+                        if shared.is_idle() {
+                            shared.stop();
+                        }
+                        return Ok(machine);
+                    }
+                }
+                Err(e) => {
+                    shared.close(index, &mut machine, &mut inbox);
+                    shared.stop();
+                    return Err((machine, e));
+                }
+            }
+
+            if blocked {
+                shared.waiting[index] = true;
+                if shared.is_idle() {
+                    shared.stop();
+                }
+            }
+        }
+
+        if !blocked {
+            // The quantum ran out: let the other tasks run, then pick up what they sent
+            task::yield_now().await;
+            while let Ok(message) = inbox.try_next() {
+                let mut shared = shared.lock().unwrap();
+                if !shared.receive(index, &mut machine, &mut inbox, message) {
+                    return Ok(machine);
+                }
+            }
+            continue;
+        }
+
+        let message = inbox.next().await;
+        let mut shared = shared.lock().unwrap();
+        if !shared.receive(index, &mut machine, &mut inbox, message) {
+            return Ok(machine);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEEDBACK: &str = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+                            1005,28,6,99,0,0,5";
+
+    fn amplifiers(program: &str, phases: &[i64]) -> Network<Machine> {
+        let machines = phases
+            .iter()
+            .map(|&phase| {
+                let mut machine = Machine::try_from(program).unwrap();
+                machine.push_input(phase);
+                machine
+            })
+            .collect();
+
+        let mut network = Network::ring(machines);
+        network.machines[0].push_input(0);
+        network
+    }
+
+    #[test]
+    pub fn test_chain() {
+        let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let mut network = amplifiers(program, &[4, 3, 2, 1, 0]);
+
+        assert_eq!(network.run(0), Err(NetworkError::ZeroQuantum));
+        assert_eq!(network.run(1), Ok(Outcome::Halted));
+        assert_eq!(network.machines[4].pop_output(), Some(43210));
+    }
+
+    #[test]
+    pub fn test_feedback_loop() {
+        let mut network = amplifiers(FEEDBACK, &[9, 8, 7, 6, 5]);
+        assert_eq!(network.run(10), Ok(Outcome::Halted));
+        assert_eq!(network.machines[4].pop_output(), Some(139629729));
+
+        let mut network = amplifiers(FEEDBACK, &[9, 8, 7, 6, 5]);
+        assert_eq!(task::block_on(network.run_async(1)), Ok(Outcome::Halted));
+        assert_eq!(network.machines[4].pop_output(), Some(139629729));
+    }
+
+    #[test]
+    pub fn test_idle() {
+        // Both machines wait for each other
+        let machines = vec![
+            Machine::try_from("3,0,4,0,99").unwrap(),
+            Machine::try_from("3,0,4,0,99").unwrap(),
+        ];
+        let mut network = Network::ring(machines);
+        assert_eq!(
+            network.run(1),
+            Ok(Outcome::Idle {
+                waiting: vec![0, 1]
+            })
+        );
+
+        network.machines[1].push_input(7);
+        assert_eq!(network.run(1), Ok(Outcome::Halted));
+        assert_eq!(network.machines[0].output, [7]);
+
+        let machines = vec![
+            Machine::try_from("3,0,4,0,99").unwrap(),
+            Machine::try_from("3,0,4,0,99").unwrap(),
+        ];
+        let mut network = Network::ring(machines);
+        assert_eq!(
+            task::block_on(network.run_async(1)),
+            Ok(Outcome::Idle {
+                waiting: vec![0, 1]
+            })
+        );
+
+        network.machines[1].push_input(7);
+        assert_eq!(task::block_on(network.run_async(1)), Ok(Outcome::Halted));
+        assert_eq!(network.machines[0].output, [7]);
+    }
+
+    #[test]
+    pub fn test_undelivered() {
+        let cases = vec![
+            // The second machine halts before the first one answers
+            ("rcv a\nsnd 1", "snd 4", vec![1], vec![]),
+            // The second machine halts without reading the last value it got
+            ("snd 1\nsnd 2\nrcv a", "rcv a\nsnd 9", vec![], vec![2]),
+        ];
+
+        for (first, second, output, input) in cases {
+            for &parallel in &[false, true] {
+                let vms = vec![Vm::try_from(first).unwrap(), Vm::try_from(second).unwrap()];
+                let mut network = Network::ring(vms);
+                let outcome = if parallel {
+                    task::block_on(network.run_async(1))
+                } else {
+                    network.run(1)
+                };
+
+                assert_eq!(outcome, Ok(Outcome::Halted));
+                assert_eq!(network.machines[0].output, output);
+                assert_eq!(network.machines[1].input, input);
+            }
+        }
+    }
+
+    /// Machines adding their index to every value they receive, then sending it to the next
+    /// machine. The last one sends to an address outside the network.
+    fn relays() -> Vec<Vm> {
+        (0..3)
+            .map(|index| {
+                let program = format!("rcv b\nadd b {}\nsnd {}\nsnd b\njmp -4", index, index + 1);
+                Vm::try_from(program.as_str()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_addressed() {
+        for &parallel in &[false, true] {
+            let mut network = Network::addressed(relays(), 2);
+            network.machines[0].input.push_back(10);
+            let outcome = if parallel {
+                task::block_on(network.run_async(1))
+            } else {
+                network.run(3)
+            };
+
+            assert_eq!(
+                outcome,
+                Ok(Outcome::Idle {
+                    waiting: vec![0, 1, 2]
+                })
+            );
+            assert_eq!(
+                network.outbox,
+                vec![Packet {
+                    address: 3,
+                    values: vec![13]
+                }]
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_non_blocking() {
+        // Sends every non-negative value it reads to `address`, reading -1 when there is no input
+        let forward = |address: i64| {
+            let program = format!("3,20,1007,20,0,21,1005,21,0,104,{},4,20,1105,1,0", address);
+            let mut machine = Machine::try_from(program.as_str()).unwrap();
+            machine.default_input = Some(-1);
+            machine
+        };
+
+        for &parallel in &[false, true] {
+            let mut network = Network::addressed(vec![forward(1), forward(255)], 2);
+            network.machines[0].push_input(5);
+            let outcome = if parallel {
+                task::block_on(network.run_async(1))
+            } else {
+                network.run(2)
+            };
+
+            assert_eq!(
+                outcome,
+                Ok(Outcome::Idle {
+                    waiting: vec![0, 1]
+                })
+            );
+            assert_eq!(
+                network.outbox,
+                vec![Packet {
+                    address: 255,
+                    values: vec![5]
+                }]
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_errors() {
+        let mut network = Network::ring(vec![
+            Vm::try_from("rcv a\nmod b a").unwrap(),
+            Vm::try_from("snd 0\nrcv a").unwrap(),
+        ]);
+        assert_eq!(
+            network.run(1),
+            Err(NetworkError::Node {
+                index: 0,
+                error: VmError::DivisionByZero { ip: 1 }
+            })
+        );
+
+        // The second machine never blocks, it gives up between two quanta
+        let mut network = Network::ring(vec![
+            Vm::try_from("rcv a\nmod b a").unwrap(),
+            Vm::try_from("snd 0\njmp +0").unwrap(),
+        ]);
+        assert_eq!(
+            task::block_on(network.run_async(0)),
+            Err(NetworkError::ZeroQuantum)
+        );
+        assert_eq!(
+            task::block_on(network.run_async(10)),
+            Err(NetworkError::Node {
+                index: 0,
+                error: VmError::DivisionByZero { ip: 1 }
+            })
+        );
+        assert_eq!(network.machines.len(), 2);
+        assert_eq!(network.machines[1].ip, 1);
+    }
+}
@@ -0,0 +1,179 @@
+use std::{path::PathBuf, sync::Arc, time::Instant};
+
+use async_std::task;
+use futures::stream::{self, StreamExt};
+
+use crate::{
+    answer::Answer,
+    check::{ExpectedAnswers, Verdict},
+    inputs,
+    solution::{Registry, Solution},
+    timing::{self, Measurement, Stage},
+};
+
+/// What to run, and how
+pub struct RunConfig {
+    pub year: u32,
+    pub days: Vec<u32>,
+    pub parts: Vec<u8>,
+    pub session_file: Option<PathBuf>,
+    /// Number of times each part is run
+    pub runs: usize,
+    /// Maximum number of parts running at the same time
+    pub jobs: usize,
+    /// Print a timing report once every part is done
+    pub report: bool,
+    /// Compare answers against these when set
+    pub expected: Option<ExpectedAnswers>,
+}
+
+/// Outcome of running a single part
+pub struct PartResult {
+    pub day: u32,
+    pub part: u8,
+    pub answer: Answer,
+    /// Only set when checking answers
+    pub verdict: Option<Verdict>,
+    pub measurement: Measurement,
+}
+
+/// Runs the requested parts of every requested day, up to `config.jobs` at a time. Days without
+/// a registered solution are skipped, as are days whose input can't be loaded, which get reported.
+/// Answers are printed in day and part order, whatever order they complete in.
+pub async fn run(registry: &Registry, config: &RunConfig) -> Vec<PartResult> {
+    let year = config.year;
+
+    let mut measurements = vec![];
+    let mut jobs = vec![];
+    let mut skipped = 0;
+
+    for solution in registry
+        .iter()
+        .filter(|s| s.year() == year && config.days.contains(&s.day()))
+    {
+        let day = solution.day();
+
+        let start = Instant::now();
+        let input = match inputs::load(year, day, &config.session_file) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Day {}: skipped, couldn't load input: {}", day, e);
+                skipped += 1;
+                continue;
+            }
+        };
+        measurements.push(Measurement::new(
+            year,
+            day,
+            Stage::Input,
+            vec![start.elapsed()],
+        ));
+
+        let start = Instant::now();
+        if solution.parse(&input) {
+            let mut samples = vec![start.elapsed()];
+            for _ in 1..config.runs.max(1) {
+                let start = Instant::now();
+                solution.parse(&input);
+                samples.push(start.elapsed());
+            }
+            measurements.push(Measurement::new(year, day, Stage::Parse, samples));
+        }
+
+        let input = Arc::new(input);
+        for &part in &config.parts {
+            jobs.push(run_part(
+                solution.clone(),
+                input.clone(),
+                part,
+                config.runs.max(1),
+            ));
+        }
+    }
+
+    // `buffered` only starts a job once there's room for it, and yields results in order
+    let mut results = stream::iter(jobs).buffered(config.jobs.max(1));
+    let mut done = vec![];
+
+    while let Some(mut r) = results.next().await {
+        r.verdict = config
+            .expected
+            .as_ref()
+            .map(|e| e.verdict(r.day, r.part, &r.answer));
+
+        let line = match &r.verdict {
+            Some(v) => format!("Day {}, part {}: {} ({})", r.day, r.part, r.answer, v),
+            None => format!("Day {}, part {}: {}", r.day, r.part, r.answer),
+        };
+        if r.answer.is_error() {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+
+        done.push(r);
+    }
+
+    if config.expected.is_some() {
+        let count = |f: fn(&Verdict) -> bool| {
+            done.iter()
+                .filter_map(|r| r.verdict.as_ref())
+                .filter(|v| f(v))
+                .count()
+        };
+
+        println!(
+            "\nCheck: {} passed, {} failed, {} unknown, {} skipped",
+            count(|v| *v == Verdict::Pass),
+            count(|v| matches!(v, Verdict::Fail(_))),
+            count(|v| *v == Verdict::Unknown),
+            skipped,
+        );
+    }
+
+    if config.report {
+        measurements.extend(done.iter().map(|r| r.measurement.clone()));
+        print!("\n{}", timing::report(&measurements));
+    }
+
+    done
+}
+
+async fn run_part(
+    solution: Arc<dyn Solution>,
+    input: Arc<String>,
+    part: u8,
+    runs: usize,
+) -> PartResult {
+    task::spawn(async move {
+        let solve = || match part {
+            1 => solution.part1(&input),
+            _ => solution.part2(&input),
+        };
+
+        let mut samples = Vec::with_capacity(runs);
+        let start = Instant::now();
+        let answer = solve();
+        samples.push(start.elapsed());
+
+        for _ in 1..runs {
+            let start = Instant::now();
+            solve();
+            samples.push(start.elapsed());
+        }
+
+        PartResult {
+            day: solution.day(),
+            part,
+            answer,
+            verdict: None,
+            measurement: Measurement::new(
+                solution.year(),
+                solution.day(),
+                Stage::Part(part),
+                samples,
+            ),
+        }
+    })
+    .await
+}
@@ -0,0 +1,162 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::answer::Answer;
+
+/// Solver for both parts of a single puzzle
+pub trait Solution: Send + Sync {
+    fn year(&self) -> u32;
+    fn day(&self) -> u32;
+    fn part1(&self, input: &str) -> Answer;
+    fn part2(&self, input: &str) -> Answer;
+
+    /// Only parses `input`, so that parsing can be timed on its own.
+    /// Returns `false` if the solution has no separate parsing step.
+    fn parse(&self, _input: &str) -> bool {
+        false
+    }
+}
+
+/// Parsing step of a `DaySolution`, whatever it produces
+type Parser = Box<dyn Fn(&str) + Send + Sync>;
+
+/// `Solution` built from a day module's `solve_part1` and `solve_part2` functions
+pub struct DaySolution<A, B> {
+    year: u32,
+    day: u32,
+    part1: fn(&str) -> A,
+    part2: fn(&str) -> B,
+    parse: Option<Parser>,
+}
+
+impl<A, B> DaySolution<A, B> {
+    pub fn new(year: u32, day: u32, part1: fn(&str) -> A, part2: fn(&str) -> B) -> Self {
+        DaySolution {
+            year,
+            day,
+            part1,
+            part2,
+            parse: None,
+        }
+    }
+
+    /// Sets the function both parts start with, to time it separately
+    pub fn with_parser<P: 'static>(mut self, parse: fn(&str) -> P) -> Self {
+        self.parse = Some(Box::new(move |input| drop(parse(input))));
+        self
+    }
+}
+
+impl<A: Into<Answer>, B: Into<Answer>> Solution for DaySolution<A, B> {
+    fn year(&self) -> u32 {
+        self.year
+    }
+
+    fn day(&self) -> u32 {
+        self.day
+    }
+
+    fn part1(&self, input: &str) -> Answer {
+        (self.part1)(input).into()
+    }
+
+    fn part2(&self, input: &str) -> Answer {
+        (self.part2)(input).into()
+    }
+
+    fn parse(&self, input: &str) -> bool {
+        match &self.parse {
+            Some(parse) => {
+                parse(input);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// All the available solutions, indexed by year and day
+#[derive(Default)]
+pub struct Registry {
+    solutions: BTreeMap<(u32, u32), Arc<dyn Solution>>,
+}
+
+impl Registry {
+    pub fn register<S: Solution + 'static>(&mut self, solution: S) {
+        let key = (solution.year(), solution.day());
+        if self.solutions.insert(key, Arc::new(solution)).is_some() {
+            panic!("Solution for {} day {} registered twice", key.0, key.1);
+        }
+    }
+
+    /// Registers a `DaySolution` built from the given functions
+    pub fn add<A: Into<Answer> + 'static, B: Into<Answer> + 'static>(
+        &mut self,
+        year: u32,
+        day: u32,
+        part1: fn(&str) -> A,
+        part2: fn(&str) -> B,
+    ) {
+        self.register(DaySolution::new(year, day, part1, part2));
+    }
+
+    pub fn get(&self, year: u32, day: u32) -> Option<Arc<dyn Solution>> {
+        self.solutions.get(&(year, day)).cloned()
+    }
+
+    /// Latest year with at least one solution
+    pub fn latest_year(&self) -> Option<u32> {
+        self.solutions.keys().next_back().map(|&(year, _)| year)
+    }
+
+    /// Iterates over the solutions, ordered by year then day
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Solution>> {
+        self.solutions.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double(input: &str) -> usize {
+        input.len() * 2
+    }
+
+    fn maybe(input: &str) -> Option<i32> {
+        input.parse().ok()
+    }
+
+    #[test]
+    pub fn test_registry() {
+        let mut registry = Registry::default();
+        registry.register(DaySolution::new(2020, 2, double, maybe));
+        registry.register(DaySolution::new(2019, 1, double, double));
+        registry.register(DaySolution::new(2020, 1, maybe, double));
+
+        let keys: Vec<(u32, u32)> = registry.iter().map(|s| (s.year(), s.day())).collect();
+        assert_eq!(keys, [(2019, 1), (2020, 1), (2020, 2)]);
+
+        let solution = registry.get(2020, 2).unwrap();
+        assert_eq!(solution.part1("12"), Answer::Number(4));
+        assert_eq!(solution.part2("12"), Answer::Number(12));
+        assert_eq!(registry.get(2020, 1).unwrap().part1("a"), Answer::Missing);
+        assert!(registry.get(2020, 3).is_none());
+        assert_eq!(registry.latest_year(), Some(2020));
+        assert_eq!(Registry::default().latest_year(), None);
+        assert!(!solution.parse("12"));
+    }
+
+    #[test]
+    pub fn test_parser() {
+        let solution = DaySolution::new(2020, 1, double, double).with_parser(maybe);
+        assert!(solution.parse("12"));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_register_twice() {
+        let mut registry = Registry::default();
+        registry.register(DaySolution::new(2020, 1, double, double));
+        registry.register(DaySolution::new(2020, 1, maybe, maybe));
+    }
+}
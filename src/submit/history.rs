@@ -0,0 +1,284 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use super::{Hint, Outcome, SubmitError};
+
+/// A single submission
+#[derive(Debug, PartialEq, Clone)]
+pub struct Attempt {
+    pub year: u32,
+    pub day: u32,
+    pub part: u8,
+    pub answer: String,
+    pub outcome: Outcome,
+    /// Unix timestamp of the submission
+    pub timestamp: i64,
+}
+
+/// Every submission made so far, stored as one tab-separated line per attempt:
+/// `{year}\t{day}\t{part}\t{outcome}\t{timestamp}\t{answer}`. Backslashes, tabs and new lines
+/// in answers are escaped.
+pub struct History {
+    path: PathBuf,
+    attempts: Vec<Attempt>,
+}
+
+impl History {
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<History, SubmitError> {
+        let path = path.into();
+        let content = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(SubmitError::HistoryError(e.to_string())),
+        };
+
+        let attempts = content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| parse_attempt(l).ok_or_else(|| SubmitError::HistoryError(l.to_owned())))
+            .collect::<Result<_, _>>()?;
+
+        Ok(History { path, attempts })
+    }
+
+    fn attempts(&self, year: u32, day: u32, part: u8) -> impl Iterator<Item = &Attempt> {
+        self.attempts
+            .iter()
+            .filter(move |a| a.year == year && a.day == day && a.part == part)
+    }
+
+    /// Fails if submitting `answer` at `now` is pointless according to previous attempts
+    pub fn check(
+        &self,
+        year: u32,
+        day: u32,
+        part: u8,
+        answer: &str,
+        now: i64,
+    ) -> Result<(), SubmitError> {
+        if let Some(a) = self
+            .attempts(year, day, part)
+            .find(|a| a.outcome == Outcome::Correct)
+        {
+            return Err(SubmitError::AlreadySolved(a.answer.clone()));
+        }
+
+        if let Some(a) = self
+            .attempts(year, day, part)
+            .find(|a| a.answer == answer && matches!(a.outcome, Outcome::Incorrect { .. }))
+        {
+            return Err(SubmitError::AlreadySubmitted(a.answer.clone(), a.outcome));
+        }
+
+        if let Some(last) = self.attempts(year, day, part).last() {
+            if let Some(wait) = last.outcome.wait() {
+                if last.timestamp + wait > now {
+                    return Err(SubmitError::Cooldown(last.timestamp + wait - now));
+                }
+            }
+        }
+
+        if let Ok(value) = answer.parse::<i128>() {
+            let bound = |hint| {
+                self.attempts(year, day, part)
+                    .filter(move |a| {
+                        matches!(a.outcome, Outcome::Incorrect { hint: Some(h), .. } if h == hint)
+                    })
+                    .flat_map(|a| a.answer.parse::<i128>())
+            };
+
+            if let Some(high) = bound(Hint::TooHigh).min().filter(|&h| value >= h) {
+                return Err(SubmitError::OutOfBounds(
+                    answer.to_owned(),
+                    format!("{} was already too high", high),
+                ));
+            }
+            if let Some(low) = bound(Hint::TooLow).max().filter(|&l| value <= l) {
+                return Err(SubmitError::OutOfBounds(
+                    answer.to_owned(),
+                    format!("{} was already too low", low),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn record(&mut self, attempt: Attempt) -> Result<(), SubmitError> {
+        let io_error = |e: std::io::Error| SubmitError::HistoryError(e.to_string());
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            attempt.year,
+            attempt.day,
+            attempt.part,
+            attempt.outcome.to_token(),
+            attempt.timestamp,
+            escape(&attempt.answer)
+        )
+        .map_err(io_error)?;
+
+        self.attempts.push(attempt);
+        Ok(())
+    }
+}
+
+fn parse_attempt(line: &str) -> Option<Attempt> {
+    let mut fields = line.splitn(6, '\t');
+
+    Some(Attempt {
+        year: fields.next()?.parse().ok()?,
+        day: fields.next()?.parse().ok()?,
+        part: fields.next()?.parse().ok()?,
+        outcome: fields.next()?.parse().ok()?,
+        timestamp: fields.next()?.parse().ok()?,
+        answer: unescape(fields.next()?)?,
+    })
+}
+
+fn escape(answer: &str) -> String {
+    answer
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(field: &str) -> Option<String> {
+    let mut answer = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            answer.push(c);
+            continue;
+        }
+
+        answer.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+
+    Some(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(name: &str) -> History {
+        let path = std::env::temp_dir().join(format!(
+            "aoc-submissions-{}-{}.tsv",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        History::load(path).unwrap()
+    }
+
+    fn incorrect(hint: Option<Hint>) -> Outcome {
+        Outcome::Incorrect { hint, wait: None }
+    }
+
+    fn attempt(answer: &str, outcome: Outcome, timestamp: i64) -> Attempt {
+        Attempt {
+            year: 2020,
+            day: 1,
+            part: 1,
+            answer: answer.to_owned(),
+            outcome,
+            timestamp,
+        }
+    }
+
+    #[test]
+    pub fn test_record_and_load() {
+        let mut h = history("record");
+        h.record(attempt("42", incorrect(Some(Hint::TooLow)), 100))
+            .unwrap();
+        h.record(attempt("a b", Outcome::TooRecent(Some(30)), 110))
+            .unwrap();
+        h.record(attempt("a\tb\nc\\t", incorrect(None), 120))
+            .unwrap();
+
+        let loaded = History::load(h.path.clone()).unwrap();
+        assert_eq!(loaded.attempts, h.attempts);
+        assert_eq!(fs::read_to_string(&h.path).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    pub fn test_check() {
+        let mut h = history("check");
+        assert_eq!(h.check(2020, 1, 1, "50", 0), Ok(()));
+
+        h.record(attempt("10", incorrect(Some(Hint::TooLow)), 0))
+            .unwrap();
+        h.record(attempt("90", incorrect(Some(Hint::TooHigh)), 100))
+            .unwrap();
+        h.record(attempt("50", incorrect(None), 200)).unwrap();
+
+        assert_eq!(
+            h.check(2020, 1, 1, "50", 1000),
+            Err(SubmitError::AlreadySubmitted(
+                "50".to_string(),
+                incorrect(None)
+            ))
+        );
+        assert!(matches!(
+            h.check(2020, 1, 1, "95", 1000),
+            Err(SubmitError::OutOfBounds(_, _))
+        ));
+        assert!(matches!(
+            h.check(2020, 1, 1, "5", 1000),
+            Err(SubmitError::OutOfBounds(_, _))
+        ));
+        assert_eq!(h.check(2020, 1, 1, "60", 1000), Ok(()));
+        assert_eq!(h.check(2020, 1, 2, "95", 1000), Ok(()));
+
+        h.record(attempt("60", Outcome::TooRecent(Some(60)), 1000))
+            .unwrap();
+        assert_eq!(
+            h.check(2020, 1, 1, "60", 1020),
+            Err(SubmitError::Cooldown(40))
+        );
+        assert_eq!(h.check(2020, 1, 1, "60", 1060), Ok(()));
+
+        h.record(attempt(
+            "60",
+            Outcome::Incorrect {
+                hint: None,
+                wait: Some(60),
+            },
+            1060,
+        ))
+        .unwrap();
+        assert_eq!(
+            h.check(2020, 1, 1, "70", 1100),
+            Err(SubmitError::Cooldown(20))
+        );
+        assert_eq!(h.check(2020, 1, 1, "70", 1120), Ok(()));
+
+        h.record(attempt("70", Outcome::Correct, 1120)).unwrap();
+        assert_eq!(
+            h.check(2020, 1, 1, "61", 2000),
+            Err(SubmitError::AlreadySolved("70".to_string()))
+        );
+    }
+}
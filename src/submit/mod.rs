@@ -0,0 +1,374 @@
+pub mod history;
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{
+    credentials::CredentialProvider,
+    inputs::{
+        http::{Client, AOC_URL},
+        InputError,
+    },
+};
+use history::{Attempt, History};
+
+/// File in which every submission is recorded
+pub const HISTORY_FILE: &str = "./answers/submissions.tsv";
+
+/// Hint given by adventofcode.com along with a wrong answer
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Hint {
+    TooHigh,
+    TooLow,
+}
+
+/// Response to a submission
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Outcome {
+    Correct,
+    /// Wrong answer, along with the seconds to wait before the next attempt if any
+    Incorrect {
+        hint: Option<Hint>,
+        wait: Option<i64>,
+    },
+    /// Submitted too soon after the previous attempt, holds the seconds left to wait
+    TooRecent(Option<i64>),
+    /// The part is either locked or already solved
+    WrongLevel,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Correct => write!(f, "right answer"),
+            Outcome::Incorrect { hint, wait } => {
+                write!(f, "wrong answer")?;
+                match hint {
+                    Some(Hint::TooHigh) => write!(f, ", too high")?,
+                    Some(Hint::TooLow) => write!(f, ", too low")?,
+                    None => {}
+                }
+                match wait {
+                    Some(s) => write!(f, ", wait {}s", s),
+                    None => Ok(()),
+                }
+            }
+            Outcome::TooRecent(Some(s)) => write!(f, "answered too recently, wait {}s", s),
+            Outcome::TooRecent(None) => write!(f, "answered too recently"),
+            Outcome::WrongLevel => write!(f, "part is locked or already solved"),
+        }
+    }
+}
+
+/// Compact form used in the history file
+impl FromStr for Outcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let incorrect = |hint: Option<Hint>, wait: Option<&str>| {
+            let wait = match wait {
+                Some(w) => Some(w.parse().map_err(|_| format!("invalid outcome: {}", s))?),
+                None => None,
+            };
+
+            Ok(Outcome::Incorrect { hint, wait })
+        };
+
+        // Wrong answers may be followed by the time to wait, e.g. `too-low;wait:60`
+        let mut parts = s.splitn(2, ";wait:");
+        let token = parts.next().unwrap_or_default();
+        let wait = parts.next();
+
+        match token {
+            "correct" if wait.is_none() => Ok(Outcome::Correct),
+            "incorrect" => incorrect(None, wait),
+            "too-high" => incorrect(Some(Hint::TooHigh), wait),
+            "too-low" => incorrect(Some(Hint::TooLow), wait),
+            "wrong-level" if wait.is_none() => Ok(Outcome::WrongLevel),
+            "too-recent" => Ok(Outcome::TooRecent(None)),
+            s if s.starts_with("wait:") => s[5..]
+                .parse()
+                .map(|w| Outcome::TooRecent(Some(w)))
+                .map_err(|_| format!("invalid outcome: {}", s)),
+            s => Err(format!("invalid outcome: {}", s)),
+        }
+    }
+}
+
+impl Outcome {
+    pub fn to_token(self) -> String {
+        match self {
+            Outcome::Correct => "correct".to_owned(),
+            Outcome::Incorrect { hint, wait } => {
+                let token = match hint {
+                    None => "incorrect",
+                    Some(Hint::TooHigh) => "too-high",
+                    Some(Hint::TooLow) => "too-low",
+                };
+
+                match wait {
+                    Some(w) => format!("{};wait:{}", token, w),
+                    None => token.to_owned(),
+                }
+            }
+            Outcome::WrongLevel => "wrong-level".to_owned(),
+            Outcome::TooRecent(None) => "too-recent".to_owned(),
+            Outcome::TooRecent(Some(w)) => format!("wait:{}", w),
+        }
+    }
+
+    /// Seconds to wait before the next attempt, if adventofcode.com asked for it
+    pub fn wait(self) -> Option<i64> {
+        match self {
+            Outcome::Incorrect { wait, .. } | Outcome::TooRecent(wait) => wait,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum SubmitError {
+    #[error("part is already solved, the answer was {0}")]
+    AlreadySolved(String),
+    #[error("{0} was already submitted: {1}")]
+    AlreadySubmitted(String, Outcome),
+    #[error("{0} can't be right: {1}")]
+    OutOfBounds(String, String),
+    #[error("submitted too recently, wait {0}s before trying again")]
+    Cooldown(i64),
+    #[error("couldn't understand the response from adventofcode.com")]
+    UnknownResponse,
+    #[error("history error: {0}")]
+    HistoryError(String),
+    #[error("{0}")]
+    RequestError(#[from] InputError),
+}
+
+/// Extracts the outcome from the page returned after submitting an answer
+pub fn parse_response(body: &str) -> Option<Outcome> {
+    lazy_static! {
+        static ref WAIT_RE: Regex =
+            Regex::new(r"You have (?:(?P<m>\d+)m ?)?(?:(?P<s>\d+)s )?left to wait").unwrap();
+        static ref PENALTY_RE: Regex =
+            Regex::new(r"(?i)please wait (?P<n>one|\d+) minutes? before trying again").unwrap();
+    }
+
+    if body.contains("That's the right answer") {
+        Some(Outcome::Correct)
+    } else if body.contains("That's not the right answer") {
+        Some(Outcome::Incorrect {
+            hint: if body.contains("your answer is too high") {
+                Some(Hint::TooHigh)
+            } else if body.contains("your answer is too low") {
+                Some(Hint::TooLow)
+            } else {
+                None
+            },
+            wait: PENALTY_RE.captures(body).map(|c| match &c["n"] {
+                "one" => 60,
+                n => n.parse::<i64>().unwrap_or(1) * 60,
+            }),
+        })
+    } else if body.contains("You gave an answer too recently") {
+        Some(Outcome::TooRecent(WAIT_RE.captures(body).map(|c| {
+            let get = |n| c.name(n).map_or(0, |m| m.as_str().parse().unwrap_or(0));
+            get("m") * 60 + get("s")
+        })))
+    } else if body.contains("You don't seem to be solving the right level") {
+        Some(Outcome::WrongLevel)
+    } else {
+        None
+    }
+}
+
+/// Posts answers to adventofcode.com
+pub struct Submitter {
+    base_url: String,
+    credentials: CredentialProvider,
+    client: Client,
+}
+
+impl Submitter {
+    pub fn new(credentials: CredentialProvider) -> Submitter {
+        Submitter {
+            base_url: AOC_URL.to_owned(),
+            credentials,
+            client: Client::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_base_url(mut self, base_url: &str) -> Submitter {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn submit(
+        &self,
+        year: u32,
+        day: u32,
+        part: u8,
+        answer: &str,
+    ) -> Result<Outcome, SubmitError> {
+        let session = self.credentials.session().map_err(InputError::from)?;
+        let url = format!("{}/{}/day/{}/answer", self.base_url, year, day);
+        let fields = format!("level={}&answer={}", part, self.client.url_encode(answer));
+
+        let body = self
+            .client
+            .request(&url, &session, Some(&fields), year, day)?;
+        parse_response(&body).ok_or(SubmitError::UnknownResponse)
+    }
+}
+
+/// Submits `answer` unless the history shows it can't be right, then records the attempt
+pub fn submit(
+    year: u32,
+    day: u32,
+    part: u8,
+    answer: &str,
+    session_file: &Option<PathBuf>,
+) -> Result<Outcome, SubmitError> {
+    let mut history = History::load(HISTORY_FILE)?;
+    let now = chrono::Utc::now().timestamp();
+
+    history.check(year, day, part, answer, now)?;
+
+    let outcome = Submitter::new(CredentialProvider::new(session_file.clone()))
+        .submit(year, day, part, answer)?;
+
+    history.record(Attempt {
+        year,
+        day,
+        part,
+        answer: answer.to_owned(),
+        outcome,
+        timestamp: now,
+    })?;
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs::stub;
+
+    #[test]
+    pub fn test_parse_response() {
+        assert_eq!(
+            parse_response("<article><p>That's the right answer!  You are one gold star closer to saving your vacation.</p></article>"),
+            Some(Outcome::Correct)
+        );
+        assert_eq!(
+            parse_response("<article><p>That's not the right answer; your answer is too high.  If you're stuck, make sure you're using the full input data.</p></article>"),
+            Some(Outcome::Incorrect {
+                hint: Some(Hint::TooHigh),
+                wait: None
+            })
+        );
+        assert_eq!(
+            parse_response("<article><p>That's not the right answer.  If you're stuck, make sure you're using the full input data.</p></article>"),
+            Some(Outcome::Incorrect {
+                hint: None,
+                wait: None
+            })
+        );
+        assert_eq!(
+            parse_response("<article><p>That's not the right answer; your answer is too low.  If you're stuck, make sure you're using the full input data.  Please wait one minute before trying again.</p></article>"),
+            Some(Outcome::Incorrect {
+                hint: Some(Hint::TooLow),
+                wait: Some(60)
+            })
+        );
+        assert_eq!(
+            parse_response("<article><p>That's not the right answer.  Because you have guessed incorrectly 4 times on this puzzle, please wait 5 minutes before trying again.</p></article>"),
+            Some(Outcome::Incorrect {
+                hint: None,
+                wait: Some(300)
+            })
+        );
+        assert_eq!(
+            parse_response("<article><p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 4m 10s left to wait.</p></article>"),
+            Some(Outcome::TooRecent(Some(250)))
+        );
+        assert_eq!(
+            parse_response("<article><p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 35s left to wait.</p></article>"),
+            Some(Outcome::TooRecent(Some(35)))
+        );
+        assert_eq!(
+            parse_response("<article><p>You don't seem to be solving the right level.  Did you already complete it?</p></article>"),
+            Some(Outcome::WrongLevel)
+        );
+        assert_eq!(parse_response("<html></html>"), None);
+    }
+
+    #[test]
+    pub fn test_outcome_tokens() {
+        for &o in &[
+            Outcome::Correct,
+            Outcome::Incorrect {
+                hint: None,
+                wait: None,
+            },
+            Outcome::Incorrect {
+                hint: Some(Hint::TooHigh),
+                wait: None,
+            },
+            Outcome::Incorrect {
+                hint: Some(Hint::TooLow),
+                wait: Some(60),
+            },
+            Outcome::TooRecent(None),
+            Outcome::TooRecent(Some(42)),
+            Outcome::WrongLevel,
+        ] {
+            assert_eq!(o.to_token().parse(), Ok(o));
+        }
+        assert!("correct;wait:60".parse::<Outcome>().is_err());
+        assert!("too-low;wait:x".parse::<Outcome>().is_err());
+    }
+
+    #[test]
+    pub fn test_submit() {
+        let (url, server) =
+            stub::serve(vec![
+            (200, "<article><p>That's not the right answer; your answer is too low.</p></article>"),
+            (200, "<article><p>That's the right answer!</p></article>"),
+            (200, "<html>Something else</html>"),
+            (400, ""),
+        ]);
+        let submitter =
+            Submitter::new(CredentialProvider::with_token("abc123")).with_base_url(&url);
+
+        assert_eq!(
+            submitter.submit(2020, 1, 2, "1000"),
+            Ok(Outcome::Incorrect {
+                hint: Some(Hint::TooLow),
+                wait: None
+            })
+        );
+        assert_eq!(
+            submitter.submit(2020, 1, 2, "241861950"),
+            Ok(Outcome::Correct)
+        );
+        assert_eq!(
+            submitter.submit(2020, 1, 2, "1"),
+            Err(SubmitError::UnknownResponse)
+        );
+        assert_eq!(
+            submitter.submit(2020, 1, 2, "1"),
+            Err(SubmitError::RequestError(InputError::BadSession))
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/2020/day/1/answer");
+        assert_eq!(requests[0].header("Cookie"), Some("session=abc123"));
+        assert!(requests[0].header("User-Agent").is_some());
+        assert_eq!(requests[0].body, "level=2&answer=1000");
+    }
+}
@@ -0,0 +1,117 @@
+use std::{cmp::Reverse, fmt, time::Duration};
+
+/// Step of a run that gets timed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Stage {
+    /// Reading, or downloading, the input
+    Input,
+    /// Parsing the input, for solutions that expose their parser
+    Parse,
+    Part(u8),
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Input => write!(f, "input"),
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part(p) => write!(f, "part {}", p),
+        }
+    }
+}
+
+/// Wall-clock durations of every run of a stage
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub year: u32,
+    pub day: u32,
+    pub stage: Stage,
+    samples: Vec<Duration>,
+}
+
+impl Measurement {
+    pub fn new(year: u32, day: u32, stage: Stage, mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+
+        Measurement {
+            year,
+            day,
+            stage,
+            samples,
+        }
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples.first().cloned().unwrap_or_default()
+    }
+
+    pub fn median(&self) -> Duration {
+        self.samples
+            .get(self.samples.len() / 2)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples.last().cloned().unwrap_or_default()
+    }
+}
+
+/// Table of every measurement, most expensive first
+pub fn report(measurements: &[Measurement]) -> String {
+    let mut sorted: Vec<&Measurement> = measurements.iter().collect();
+    sorted.sort_by_key(|m| Reverse(m.median()));
+
+    let mut s = format!(
+        "{:>4} {:>3}  {:<6}  {:>12}  {:>12}  {:>12}  {:>4}\n",
+        "Year", "Day", "Stage", "Min", "Median", "Max", "Runs"
+    );
+    for m in sorted {
+        s += &format!(
+            "{:>4} {:>3}  {:<6}  {:>12}  {:>12}  {:>12}  {:>4}\n",
+            m.year,
+            m.day,
+            m.stage.to_string(),
+            format!("{:.2?}", m.min()),
+            format!("{:.2?}", m.median()),
+            format!("{:.2?}", m.max()),
+            m.samples.len()
+        );
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(v: &[u64]) -> Vec<Duration> {
+        v.iter().map(|&n| Duration::from_millis(n)).collect()
+    }
+
+    #[test]
+    pub fn test_statistics() {
+        let m = Measurement::new(2020, 15, Stage::Part(2), ms(&[30, 10, 50, 20, 40]));
+
+        assert_eq!(m.min(), Duration::from_millis(10));
+        assert_eq!(m.median(), Duration::from_millis(30));
+        assert_eq!(m.max(), Duration::from_millis(50));
+    }
+
+    #[test]
+    pub fn test_report() {
+        let report = report(&[
+            Measurement::new(2020, 1, Stage::Input, ms(&[1])),
+            Measurement::new(2020, 23, Stage::Part(2), ms(&[900, 1100, 1000])),
+            Measurement::new(2020, 1, Stage::Part(1), ms(&[5])),
+        ]);
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("Median"));
+        assert!(lines[1].contains("23  part 2") && lines[1].contains("1.00s"));
+        assert!(lines[2].contains("1  part 1"));
+        assert!(lines[3].contains("1  input"));
+    }
+}
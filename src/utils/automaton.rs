@@ -0,0 +1,185 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+/// Coordinates of a cell, which can be moved by an offset of the same type
+pub trait Cell: Clone + Eq + Hash {
+    fn translate(&self, offset: &Self) -> Self;
+}
+
+/// Coordinates in `N` dimensions
+impl<const N: usize> Cell for [i64; N] {
+    fn translate(&self, offset: &Self) -> Self {
+        let mut cell = *self;
+        for (c, d) in cell.iter_mut().zip(offset) {
+            *c += d;
+        }
+        cell
+    }
+}
+
+impl Cell for (i64, i64) {
+    fn translate(&self, offset: &Self) -> Self {
+        (self.0 + offset.0, self.1 + offset.1)
+    }
+}
+
+/// Offsets of the cells surrounding a cell in `N` dimensions, diagonals included
+pub fn moore<const N: usize>() -> Vec<[i64; N]> {
+    (0..3_usize.pow(N as u32))
+        .map(|mut n| {
+            let mut offset = [0; N];
+            for d in offset.iter_mut() {
+                *d = (n % 3) as i64 - 1;
+                n /= 3;
+            }
+            offset
+        })
+        .filter(|o| o.iter().any(|&d| d != 0))
+        .collect()
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid rules `{0}`, expected something like `B3/S23`")]
+pub struct RulesError(String);
+
+/// Numbers of active neighbours for which a cell becomes or stays active
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rules {
+    pub birth: Vec<usize>,
+    pub survival: Vec<usize>,
+}
+
+/// Parses the `B3/S23` notation
+impl FromStr for Rules {
+    type Err = RulesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || RulesError(s.to_owned());
+        let counts = |part: &str, prefix: char| -> Result<Vec<usize>, RulesError> {
+            let digits = part.strip_prefix(prefix).ok_or_else(error)?;
+            digits
+                .chars()
+                .map(|c| c.to_digit(10).map(|d| d as usize).ok_or_else(error))
+                .collect()
+        };
+
+        let mut parts = s.splitn(2, '/');
+        let birth = counts(parts.next().unwrap_or_default(), 'B')?;
+        let survival = counts(parts.next().ok_or_else(error)?, 'S')?;
+
+        Ok(Rules { birth, survival })
+    }
+}
+
+/// Cellular automaton over an unbounded space, only storing its active cells
+#[derive(Debug, Clone)]
+pub struct Automaton<C: Cell> {
+    pub active: HashSet<C>,
+    rules: Rules,
+    neighbourhood: Vec<C>,
+}
+
+impl<C: Cell> Automaton<C> {
+    /// `neighbourhood` holds the offsets from a cell to each of its neighbours
+    pub fn new(active: HashSet<C>, rules: Rules, neighbourhood: Vec<C>) -> Automaton<C> {
+        Automaton {
+            active,
+            rules,
+            neighbourhood,
+        }
+    }
+
+    /// Computes the next generation
+    pub fn step(&mut self) {
+        let mut neighbours: HashMap<C, usize> = HashMap::new();
+        for cell in &self.active {
+            // Active cells without any active neighbour may survive too
+            neighbours.entry(cell.clone()).or_insert(0);
+
+            for offset in &self.neighbourhood {
+                *neighbours.entry(cell.translate(offset)).or_insert(0) += 1;
+            }
+        }
+
+        let active = &self.active;
+        let rules = &self.rules;
+        self.active = neighbours
+            .into_iter()
+            .filter(|(cell, count)| {
+                if active.contains(cell) {
+                    rules.survival.contains(count)
+                } else {
+                    rules.birth.contains(count)
+                }
+            })
+            .map(|(cell, _)| cell)
+            .collect();
+    }
+
+    /// Computes the next `generations` generations, returning the number of active cells
+    pub fn run(&mut self, generations: usize) -> usize {
+        for _ in 0..generations {
+            self.step();
+        }
+
+        self.active.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_moore() {
+        assert_eq!(moore::<1>(), [[-1], [1]]);
+        assert_eq!(moore::<2>().len(), 8);
+        assert_eq!(moore::<4>().len(), 80);
+    }
+
+    #[test]
+    pub fn test_rules() {
+        assert_eq!(
+            "B3/S23".parse(),
+            Ok(Rules {
+                birth: vec![3],
+                survival: vec![2, 3]
+            })
+        );
+        assert_eq!(
+            "B2/S".parse(),
+            Ok(Rules {
+                birth: vec![2],
+                survival: vec![]
+            })
+        );
+        assert_eq!(
+            "S23/B3".parse::<Rules>(),
+            Err(RulesError("S23/B3".to_owned()))
+        );
+        assert!("B3".parse::<Rules>().is_err());
+    }
+
+    #[test]
+    pub fn test_blinker() {
+        let active = vec![[0, -1], [0, 0], [0, 1]];
+        let mut automaton = Automaton::new(
+            active.iter().cloned().collect(),
+            "B3/S23".parse().unwrap(),
+            moore(),
+        );
+
+        automaton.step();
+        let mut horizontal: Vec<_> = automaton.active.iter().cloned().collect();
+        horizontal.sort();
+        assert_eq!(horizontal, [[-1, 0], [0, 0], [1, 0]]);
+
+        assert_eq!(automaton.run(1), 3);
+        assert_eq!(automaton.active, active.into_iter().collect());
+    }
+}
@@ -0,0 +1,306 @@
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use thiserror::Error;
+
+/// Position of a cell, as `(x, y)`. Signed so that neighbours of the borders can be expressed.
+pub type Point = (isize, isize);
+
+/// Orthogonal directions
+#[allow(dead_code)]
+pub const DIRECTIONS4: [Point; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Orthogonal and diagonal directions
+pub const DIRECTIONS8: [Point; 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum GridError {
+    #[error("grid is empty")]
+    Empty,
+    #[error("line {line} has {found} cells instead of {expected}")]
+    RaggedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("invalid cell `{c}` at line {line}, column {column}")]
+    InvalidCell { line: usize, column: usize, c: char },
+}
+
+/// Rectangular grid of cells, stored row by row
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Grid made of `cells`, given row by row
+    pub fn new(width: usize, cells: Vec<T>) -> Grid<T> {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "{} cells can't make rows of {}",
+            cells.len(),
+            width
+        );
+
+        Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    // No day needs the width of its grid yet
+    #[allow(dead_code)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, (x, y): Point) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            None
+        } else {
+            Some(y as usize * self.width + x as usize)
+        }
+    }
+
+    /// Cell at `position`, or `None` if it lies outside of the grid
+    pub fn get(&self, position: Point) -> Option<&T> {
+        self.index(position).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, position: Point) -> Option<&mut T> {
+        self.index(position).map(move |i| &mut self.cells[i])
+    }
+
+    /// Cell at `position`, the grid repeating itself infinitely in every direction
+    pub fn get_wrapping(&self, (x, y): Point) -> &T {
+        let x = x.rem_euclid(self.width as isize);
+        let y = y.rem_euclid(self.height as isize);
+        &self.cells[y as usize * self.width + x as usize]
+    }
+
+    /// Every cell along with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, c)| (((i % width) as isize, (i / width) as isize), c))
+    }
+
+    /// Cells next to `position` in the given directions, skipping the ones outside of the grid
+    pub fn neighbours<'a>(
+        &'a self,
+        (x, y): Point,
+        directions: &'a [Point],
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        directions.iter().filter_map(move |&(dx, dy)| {
+            let position = (x + dx, y + dy);
+            self.get(position).map(|c| (position, c))
+        })
+    }
+
+    /// Orthogonal neighbours of `position`
+    #[allow(dead_code)]
+    pub fn neighbours4(&self, position: Point) -> impl Iterator<Item = (Point, &T)> {
+        self.neighbours(position, &DIRECTIONS4)
+    }
+
+    /// Orthogonal and diagonal neighbours of `position`
+    pub fn neighbours8(&self, position: Point) -> impl Iterator<Item = (Point, &T)> {
+        self.neighbours(position, &DIRECTIONS8)
+    }
+
+    /// Cells met when moving from `position` in `direction`, until leaving the grid.
+    /// `position` itself isn't included.
+    pub fn ray(&self, position: Point, (dx, dy): Point) -> impl Iterator<Item = (Point, &T)> {
+        let (mut x, mut y) = position;
+        std::iter::from_fn(move || {
+            x += dx;
+            y += dy;
+            self.get((x, y)).map(|c| ((x, y), c))
+        })
+    }
+
+    /// Grid of the same size, each cell being computed from the cell at the same position
+    pub fn map<U, F: FnMut(Point, &T) -> U>(&self, mut f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.iter().map(|(p, c)| f(p, c)).collect(),
+        }
+    }
+}
+
+/// Parses one cell per character, one row per line
+impl<T: TryFrom<char>> FromStr for Grid<T> {
+    type Err = GridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut width = None;
+        let mut cells = vec![];
+
+        for (y, line) in s.lines().enumerate() {
+            let len = line.chars().count();
+            match width {
+                Some(expected) if expected != len => {
+                    return Err(GridError::RaggedLine {
+                        line: y + 1,
+                        expected,
+                        found: len,
+                    })
+                }
+                _ => width = Some(len),
+            }
+
+            for (x, c) in line.chars().enumerate() {
+                cells.push(T::try_from(c).map_err(|_| GridError::InvalidCell {
+                    line: y + 1,
+                    column: x + 1,
+                    c,
+                })?);
+            }
+        }
+
+        match width {
+            Some(width) if width > 0 => Ok(Grid::new(width, cells)),
+            _ => Err(GridError::Empty),
+        }
+    }
+}
+
+/// Prints one row per line, without a trailing new line
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.cells.chunks(self.width).enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cell that only accepts dots
+    #[derive(Debug, PartialEq)]
+    struct Dot;
+
+    impl TryFrom<char> for Dot {
+        type Error = ();
+
+        fn try_from(c: char) -> Result<Self, Self::Error> {
+            if c == '.' {
+                Ok(Dot)
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    const GRID: &str = "#..
+.#.
+..#
+#.#";
+
+    #[test]
+    pub fn test_parse() {
+        let grid: Grid<char> = GRID.parse().unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        assert_eq!(grid.get((1, 1)), Some(&'#'));
+        assert_eq!(grid.get((2, 0)), Some(&'.'));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, -1)), None);
+        assert_eq!(grid.to_string(), GRID);
+
+        assert_eq!("".parse::<Grid<char>>(), Err(GridError::Empty));
+        assert_eq!(
+            "##\n#".parse::<Grid<char>>(),
+            Err(GridError::RaggedLine {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            "..\n.x".parse::<Grid<Dot>>(),
+            Err(GridError::InvalidCell {
+                line: 2,
+                column: 2,
+                c: 'x'
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_neighbours() {
+        let grid: Grid<char> = GRID.parse().unwrap();
+        let positions = |n: Vec<(Point, &char)>| n.into_iter().map(|(p, _)| p).collect::<Vec<_>>();
+
+        assert_eq!(
+            positions(grid.neighbours4((0, 0)).collect()),
+            [(1, 0), (0, 1)]
+        );
+        assert_eq!(
+            grid.neighbours4((1, 1)).filter(|(_, &c)| c == '#').count(),
+            0
+        );
+        assert_eq!(
+            positions(grid.neighbours8((0, 0)).collect()),
+            [(1, 0), (0, 1), (1, 1)]
+        );
+        assert_eq!(
+            grid.neighbours8((1, 1)).filter(|(_, &c)| c == '#').count(),
+            2
+        );
+        assert_eq!(grid.neighbours8((2, 3)).count(), 3);
+        assert_eq!(
+            positions(grid.neighbours((1, 1), &[(0, 2), (0, 3)]).collect()),
+            [(1, 3)]
+        );
+    }
+
+    #[test]
+    pub fn test_ray() {
+        let grid: Grid<char> = GRID.parse().unwrap();
+        let ray: Vec<Point> = grid.ray((0, 0), (1, 1)).map(|(p, _)| p).collect();
+        assert_eq!(ray, [(1, 1), (2, 2)]);
+
+        assert_eq!(
+            grid.ray((2, 1), (0, 1)).find(|(_, &c)| c == '#'),
+            Some(((2, 2), &'#'))
+        );
+        assert_eq!(grid.ray((0, 0), (-1, 0)).next(), None);
+    }
+
+    #[test]
+    pub fn test_wrapping() {
+        let grid: Grid<char> = GRID.parse().unwrap();
+        assert_eq!(grid.get_wrapping((4, 1)), &'#');
+        assert_eq!(grid.get_wrapping((-1, -1)), &'#');
+        assert_eq!(grid.get_wrapping((3, 5)), &'.');
+    }
+}
@@ -1,3 +1,3 @@
-#[macro_use]
-pub mod macros;
+pub mod automaton;
 pub mod crt;
+pub mod grid;
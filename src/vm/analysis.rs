@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+
+use super::{
+    instruction::Instruction,
+    listing::jump_target,
+    op::Op::*,
+    operand::Operand::*,
+    repair::{Mutation, Patch},
+};
+
+/// Control-flow graph of a program. Nodes are instruction addresses, plus an extra exit node
+/// standing for the address right after the last instruction.
+///
+/// Jumps to register offsets can't be resolved statically and are left out of the graph, as are
+/// jumps leaving the program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cfg {
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+/// Addresses the instruction at `index` may hand control to, in a program of `len` instructions
+pub fn successors(index: usize, instruction: &Instruction, len: usize) -> Vec<usize> {
+    let (falls_through, jumps) = match (instruction.op, instruction.lhs) {
+        (Jmp, _) => (false, true),
+        // The condition is known when it doesn't depend on a register
+        (Jie, Immediate(v)) | (Jio, Immediate(v)) | (Jgz, Immediate(v)) => {
+            let taken = instruction.op.jumps_if(v);
+            (!taken, taken)
+        }
+        (Jie, _) | (Jio, _) | (Jgz, _) => (true, true),
+        _ => (true, false),
+    };
+
+    let mut successors = Vec::with_capacity(2);
+    if falls_through {
+        successors.push(index + 1);
+    }
+
+    if jumps {
+        match jump_target(index, instruction) {
+            Some(target) if target >= 0 && target as usize <= len => {
+                successors.push(target as usize)
+            }
+            _ => {}
+        }
+    }
+
+    successors.dedup();
+    successors
+}
+
+impl Cfg {
+    pub fn new(instructions: &[Instruction]) -> Cfg {
+        let len = instructions.len();
+        let mut predecessors = vec![vec![]; len + 1];
+        let mut successors_list = vec![vec![]; len + 1];
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            let next = successors(index, instruction, len);
+            for &s in &next {
+                predecessors[s].push(index);
+            }
+            successors_list[index] = next;
+        }
+
+        Cfg {
+            successors: successors_list,
+            predecessors,
+        }
+    }
+
+    /// Node standing for the program termination
+    pub fn exit(&self) -> usize {
+        self.successors.len() - 1
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    /// Nodes that can be reached from the entry point
+    pub fn reachable(&self) -> Vec<bool> {
+        walk(&self.successors, 0)
+    }
+
+    /// Nodes from which the exit can be reached
+    pub fn reaches_exit(&self) -> Vec<bool> {
+        walk(&self.predecessors, self.exit())
+    }
+
+    /// Addresses of the instructions that can never be executed
+    pub fn unreachable(&self) -> Vec<usize> {
+        let reachable = self.reachable();
+        (0..self.exit()).filter(|&i| !reachable[i]).collect()
+    }
+
+    /// Every cycle of the graph, as the sorted addresses of a strongly connected component
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let nodes = self.successors.len();
+
+        // Kosaraju: order the nodes by DFS completion, then collect the components of the
+        // reversed graph in reverse completion order
+        let mut order = Vec::with_capacity(nodes);
+        let mut visited = vec![false; nodes];
+        for start in 0..nodes {
+            if visited[start] {
+                continue;
+            }
+
+            visited[start] = true;
+            let mut stack = vec![(start, 0)];
+            while let Some((node, child)) = stack.pop() {
+                match self.successors[node].get(child) {
+                    Some(&next) => {
+                        stack.push((node, child + 1));
+                        if !visited[next] {
+                            visited[next] = true;
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => order.push(node),
+                }
+            }
+        }
+
+        let mut component_of = vec![None; nodes];
+        let mut cycles = vec![];
+        for &root in order.iter().rev() {
+            if component_of[root].is_some() {
+                continue;
+            }
+
+            let mut component = vec![root];
+            component_of[root] = Some(root);
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                for &previous in &self.predecessors[node] {
+                    if component_of[previous].is_none() {
+                        component_of[previous] = Some(root);
+                        component.push(previous);
+                        stack.push(previous);
+                    }
+                }
+            }
+
+            if component.len() > 1 || self.successors[root].contains(&root) {
+                component.sort_unstable();
+                cycles.push(component);
+            }
+        }
+
+        cycles.sort();
+        cycles
+    }
+}
+
+/// Nodes reachable from `start` by following `edges`
+fn walk(edges: &[Vec<usize>], start: usize) -> Vec<bool> {
+    let mut seen = vec![false; edges.len()];
+    let mut queue = VecDeque::new();
+    seen[start] = true;
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        for &next in &edges[node] {
+            if !seen[next] {
+                seen[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    seen
+}
+
+/// Patches allowed by `mutations` that connect the entry point to the exit, ordered by index.
+/// Nothing gets executed, and the search is linear in the size of the program.
+///
+/// For programs without conditional jumps, these are exactly the patches that make a looping
+/// program terminate. Otherwise, the patched program may terminate.
+pub fn connecting_patches(instructions: &[Instruction], mutations: &[Mutation]) -> Vec<Patch> {
+    let cfg = Cfg::new(instructions);
+    let reachable = cfg.reachable();
+    let reaches_exit = cfg.reaches_exit();
+
+    instructions
+        .iter()
+        .enumerate()
+        // An instruction already reaching the exit doesn't need to be patched
+        .filter(|&(index, _)| reachable[index] && !reaches_exit[index])
+        .flat_map(|(index, &instruction)| {
+            mutations
+                .iter()
+                .filter_map(move |m| m.apply(instruction))
+                .map(move |instruction| Patch { index, instruction })
+        })
+        .filter(|patch| {
+            successors(patch.index, &patch.instruction, instructions.len())
+                .iter()
+                .any(|&s| reaches_exit[s])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Vm, SAMPLE_PROGRAM};
+    use std::convert::TryFrom;
+
+    #[test]
+    pub fn test_cfg() {
+        let vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        let cfg = Cfg::new(&vm.instructions);
+
+        assert_eq!(cfg.exit(), 9);
+        assert_eq!(cfg.successors(2), [6]);
+        assert_eq!(cfg.predecessors(1), [0, 4]);
+        assert_eq!(cfg.unreachable(), [5, 8]);
+        assert_eq!(cfg.cycles(), [vec![1, 2, 3, 4, 6, 7]]);
+
+        let reaches_exit = cfg.reaches_exit();
+        assert_eq!(
+            (0..=9).filter(|&i| reaches_exit[i]).collect::<Vec<_>>(),
+            [8, 9]
+        );
+    }
+
+    #[test]
+    pub fn test_conditional_jumps() {
+        let vm = Vm::try_from("jgz a +2\njmp +0\njgz 1 -2\nacc +1").unwrap();
+        let cfg = Cfg::new(&vm.instructions);
+
+        assert_eq!(cfg.successors(0), [1, 2]);
+        assert_eq!(cfg.successors(2), [0]);
+        assert_eq!(cfg.cycles(), [vec![0, 2], vec![1]]);
+        assert_eq!(cfg.unreachable(), [3]);
+    }
+
+    #[test]
+    pub fn test_connecting_patches() {
+        let vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        let mutations = [
+            Mutation::SwapOp { from: Jmp, to: Nop },
+            Mutation::SwapOp { from: Nop, to: Jmp },
+        ];
+
+        let patches = connecting_patches(&vm.instructions, &mutations);
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].index, 7);
+        assert_eq!(patches[0].instruction.op, Nop);
+
+        let vm = Vm::try_from("acc +1\njmp +1").unwrap();
+        assert_eq!(connecting_patches(&vm.instructions, &mutations), vec![]);
+    }
+}
@@ -1,23 +1,24 @@
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt};
 
 use nom::{
     branch::alt,
-    character::complete::{alpha1, char, digit1, space1},
-    combinator::{map_res, opt, recognize},
-    sequence::{terminated, tuple},
-    Finish, IResult,
+    character::complete::{alpha1, char, digit1, satisfy, space0, space1},
+    combinator::{map, map_res, opt, recognize},
+    sequence::tuple,
+    IResult,
 };
 
 use super::{
     op::Op::{self, *},
-    Vm, VmError,
+    operand::Operand::{self, *},
+    ParseError, Vm, VmError, VmState,
 };
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Instruction {
     pub op: Op,
-    pub lhs: isize,
-    pub rhs: Option<isize>,
+    pub lhs: Operand,
+    pub rhs: Option<Operand>,
 }
 
 impl Instruction {
@@ -25,27 +26,78 @@ impl Instruction {
         instruction.clone()
     }
 
-    pub fn step(self, vm: &mut Vm) {
-        if vm.debug {
-            println!("Stepping with: {:?}", self);
-        }
-
+    pub fn step(self, vm: &mut Vm) -> Result<VmState, VmError> {
         let mut increment_ip = true;
         match self.op {
             Nop => {}
             Acc => {
-                vm.acc += self.lhs;
+                vm.acc = vm
+                    .acc
+                    .checked_add(self.lhs.value(vm))
+                    .ok_or(VmError::Overflow { ip: vm.ip })?;
             }
             Jmp => {
-                vm.ip = (vm.ip as isize + self.lhs) as usize;
+                jump(vm, self.lhs.value(vm))?;
                 increment_ip = false;
             }
+            Cpy => {
+                let value = self.lhs.value(vm);
+                vm.set_register(self.rhs()?, value)?;
+            }
+            Add | Mul | Mod => {
+                let (lhs, rhs) = (self.lhs.value(vm), self.rhs()?.value(vm));
+                let value = match self.op {
+                    Add => lhs.checked_add(rhs),
+                    Mul => lhs.checked_mul(rhs),
+                    _ if rhs == 0 => return Err(VmError::DivisionByZero { ip: vm.ip }),
+                    _ => lhs.checked_rem(rhs),
+                }
+                .ok_or(VmError::Overflow { ip: vm.ip })?;
+                vm.set_register(self.lhs, value)?;
+            }
+            Jie | Jio | Jgz => {
+                if self.op.jumps_if(self.lhs.value(vm)) {
+                    jump(vm, self.rhs()?.value(vm))?;
+                    increment_ip = false;
+                }
+            }
+            Snd => {
+                let value = self.lhs.value(vm);
+                vm.output.push_back(value);
+            }
+            Rcv => match vm.input.pop_front() {
+                Some(value) => vm.set_register(self.lhs, value)?,
+                // Executed again once some input has been pushed
+                None => return Ok(VmState::AwaitingInput),
+            },
         }
 
         if increment_ip {
             vm.ip += 1;
         }
+
+        if vm.ip == vm.instructions.len() {
+            Ok(VmState::Terminated)
+        } else {
+            Ok(VmState::Running)
+        }
+    }
+
+    fn rhs(self) -> Result<Operand, VmError> {
+        self.rhs.ok_or(VmError::InvalidNumberOfOperands)
+    }
+}
+
+/// Moves the instruction pointer by `offset`
+fn jump(vm: &mut Vm, offset: isize) -> Result<(), VmError> {
+    // Jumping right after the last instruction terminates the program
+    let target = (vm.ip as isize).saturating_add(offset);
+    if target < 0 || target as usize > vm.instructions.len() {
+        return Err(VmError::JumpOutOfBounds { ip: vm.ip, target });
     }
+
+    vm.ip = target as usize;
+    Ok(())
 }
 
 fn op(input: &str) -> IResult<&str, Op> {
@@ -54,23 +106,78 @@ fn op(input: &str) -> IResult<&str, Op> {
 
 fn signed_integer(input: &str) -> IResult<&str, isize> {
     map_res(
-        recognize(tuple((alt((char('-'), char('+'))), digit1))),
+        recognize(tuple((opt(alt((char('-'), char('+')))), digit1))),
         |s: &str| s.parse::<isize>(),
     )(input)
 }
 
-fn parse_instruction(input: &str) -> IResult<&str, Result<Instruction, VmError>> {
-    let (input, op) = terminated(op, space1)(input)?;
-    let (input, lhs) = terminated(signed_integer, opt(space1))(input)?;
-    let (input, rhs) = opt(signed_integer)(input)?;
+fn operand(input: &str) -> IResult<&str, Operand> {
+    alt((
+        map(satisfy(|c| c.is_ascii_lowercase()), Register),
+        map(signed_integer, Immediate),
+    ))(input)
+}
 
-    Ok((input, Instruction::try_from((op, lhs, rhs))))
+/// Operands may be separated by a comma, as in `jie a, +4`
+fn separator(input: &str) -> IResult<&str, Option<(Option<char>, &str)>> {
+    opt(tuple((opt(char(',')), space1)))(input)
 }
 
-impl TryFrom<(Op, isize, Option<isize>)> for Instruction {
+/// Word starting `input`, as shown in parse errors
+fn token(input: &str) -> String {
+    match input.split(|c: char| c.is_whitespace() || c == ',').next() {
+        Some(word) if !word.is_empty() => word.to_owned(),
+        _ => match input.chars().next() {
+            Some(c) => c.to_string(),
+            None => "end of line".to_owned(),
+        },
+    }
+}
+
+impl Instruction {
+    /// Parses a single line of a program. Errors are reported as if the line were the first one.
+    pub fn parse(line: &str) -> Result<Instruction, ParseError> {
+        let error = |rest: &str, expected: &str| ParseError {
+            line: 1,
+            column: line[..line.len() - rest.len()].chars().count() + 1,
+            text: token(rest),
+            expected: expected.to_owned(),
+        };
+
+        let (rest, op) = op(line).map_err(|_| error(line, "an operation"))?;
+        let (rest, _) = space1::<_, ()>(rest).map_err(|_| error(rest, "a space"))?;
+        let lhs_at = rest;
+        let (rest, lhs) = operand(rest).map_err(|_| error(rest, "a register or an integer"))?;
+
+        let (rest, rhs) = match separator(rest) {
+            Ok((rest, Some(_))) if !rest.is_empty() => {
+                let (after, rhs) =
+                    operand(rest).map_err(|_| error(rest, "a register or an integer"))?;
+                (after, Some((rest, rhs)))
+            }
+            _ => (rest, None),
+        };
+
+        let (rest, _) = space0::<_, ()>(rest).unwrap_or((rest, ""));
+        if !rest.is_empty() {
+            return Err(error(rest, "end of line"));
+        }
+
+        Instruction::try_from((op, lhs, rhs.map(|(_, rhs)| rhs))).map_err(|e| match (e, rhs) {
+            (VmError::RegisterExpected, Some((rhs_at, _))) if op == Cpy => {
+                error(rhs_at, "a register")
+            }
+            (VmError::RegisterExpected, _) => error(lhs_at, "a register"),
+            (_, Some((rhs_at, _))) => error(rhs_at, "end of line"),
+            (_, None) => error(rest, "a second operand"),
+        })
+    }
+}
+
+impl TryFrom<(Op, Operand, Option<Operand>)> for Instruction {
     type Error = VmError;
 
-    fn try_from(value: (Op, isize, Option<isize>)) -> Result<Self, Self::Error> {
+    fn try_from(value: (Op, Operand, Option<Operand>)) -> Result<Self, Self::Error> {
         let i = Instruction {
             op: value.0,
             lhs: value.1,
@@ -78,15 +185,33 @@ impl TryFrom<(Op, isize, Option<isize>)> for Instruction {
         };
 
         // Check number of operands
-        match i.op {
-            Nop | Acc | Jmp => {
-                if i.rhs.is_some() {
-                    return Err(VmError::InvalidNumberOfOperands);
-                } else {
-                    Ok(i)
-                }
-            }
+        let operands = if i.rhs.is_some() { 2 } else { 1 };
+        if operands != i.op.arity() {
+            return Err(VmError::InvalidNumberOfOperands);
         }
+
+        // Check that written operands are registers
+        let destination = match i.op {
+            Cpy => i.rhs,
+            Add | Mul | Mod | Rcv => Some(i.lhs),
+            _ => None,
+        };
+        if matches!(destination, Some(Immediate(_))) {
+            return Err(VmError::RegisterExpected);
+        }
+
+        Ok(i)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let instruction = match self.rhs {
+            Some(rhs) => format!("{} {} {}", self.op, self.lhs, rhs),
+            None => format!("{} {}", self.op, self.lhs),
+        };
+
+        f.pad(&instruction)
     }
 }
 
@@ -94,10 +219,82 @@ impl TryFrom<&str> for Instruction {
     type Error = VmError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match parse_instruction(value).finish() {
-            Ok((_, Ok(i))) => Ok(i),
-            Ok((_, Err(e))) => Err(e),
-            Err(e) => Err(VmError::NomError(e.to_string())),
+        Instruction::parse(value).map_err(VmError::InvalidInstruction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_operands() {
+        assert_eq!(
+            Instruction::try_from((Cpy, Immediate(1), None)),
+            Err(VmError::InvalidNumberOfOperands)
+        );
+        assert_eq!(
+            Instruction::try_from((Nop, Immediate(0), Some(Immediate(1)))),
+            Err(VmError::InvalidNumberOfOperands)
+        );
+        assert_eq!(
+            Instruction::try_from((Add, Immediate(1), Some(Register('a')))),
+            Err(VmError::RegisterExpected)
+        );
+        assert_eq!(
+            Instruction::try_from("cpy a b"),
+            Ok(Instruction {
+                op: Cpy,
+                lhs: Register('a'),
+                rhs: Some(Register('b')),
+            })
+        );
+    }
+
+    fn parse_error(line: usize, column: usize, text: &str, expected: &str) -> ParseError {
+        ParseError {
+            line,
+            column,
+            text: text.to_owned(),
+            expected: expected.to_owned(),
+        }
+    }
+
+    #[test]
+    pub fn test_parse_errors() {
+        let errors = [
+            ("jnz +1", parse_error(1, 1, "jnz", "an operation")),
+            ("acc", parse_error(1, 4, "end of line", "a space")),
+            ("acc  +1x", parse_error(1, 8, "x", "end of line")),
+            (
+                "jmp ++2",
+                parse_error(1, 5, "++2", "a register or an integer"),
+            ),
+            (
+                "jmp +2, ?",
+                parse_error(1, 9, "?", "a register or an integer"),
+            ),
+            ("nop +0 +1", parse_error(1, 8, "+1", "end of line")),
+            (
+                "cpy 1",
+                parse_error(1, 6, "end of line", "a second operand"),
+            ),
+            ("cpy 1 2", parse_error(1, 7, "2", "a register")),
+            ("add 1 a", parse_error(1, 5, "1", "a register")),
+        ];
+
+        for (line, error) in errors.iter() {
+            assert_eq!(
+                Instruction::try_from(*line),
+                Err(VmError::InvalidInstruction(error.clone())),
+                "{}",
+                line
+            );
         }
+
+        assert_eq!(
+            Instruction::try_from("jmp x1").unwrap_err().to_string(),
+            "couldn't parse instruction: line 1, column 6: expected end of line, found `1`"
+        );
     }
 }
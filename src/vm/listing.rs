@@ -0,0 +1,178 @@
+use std::fmt;
+
+use super::{instruction::Instruction, op::Op::*, operand::Operand::*};
+
+/// Target of the jump at `index`, if the instruction can jump to a statically known address
+pub fn jump_target(index: usize, instruction: &Instruction) -> Option<isize> {
+    let offset = match instruction.op {
+        Jmp => Some(instruction.lhs),
+        Jie | Jio | Jgz => instruction.rhs,
+        _ => None,
+    };
+
+    match offset {
+        Some(Immediate(offset)) => Some(index as isize + offset),
+        _ => None,
+    }
+}
+
+/// Whether the instruction may move the instruction pointer somewhere else than the next address
+pub fn is_jump(instruction: &Instruction) -> bool {
+    matches!(instruction.op, Jmp | Jie | Jio | Jgz)
+}
+
+/// Flags the first instruction of every basic block: the program entry, jump targets and
+/// instructions following a jump
+pub fn block_starts(instructions: &[Instruction]) -> Vec<bool> {
+    let mut starts = vec![false; instructions.len()];
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        if index == 0 {
+            starts[index] = true;
+        }
+
+        if is_jump(instruction) {
+            if let Some(next) = starts.get_mut(index + 1) {
+                *next = true;
+            }
+
+            if let Some(target) = jump_target(index, instruction) {
+                if target >= 0 && (target as usize) < instructions.len() {
+                    starts[target as usize] = true;
+                }
+            }
+        }
+    }
+
+    starts
+}
+
+/// Program listing with addresses, resolved jump targets and blank lines between basic blocks
+pub struct Listing<'a>(pub &'a [Instruction]);
+
+impl fmt::Display for Listing<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let instructions = self.0;
+        let starts = block_starts(instructions);
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            if starts[index] && index > 0 {
+                writeln!(f)?;
+            }
+
+            match jump_target(index, instruction) {
+                Some(target) if target as usize == instructions.len() => {
+                    writeln!(f, "{:04}  {:<12}; -> end", index, instruction)?
+                }
+                Some(target) if target < 0 || target as usize > instructions.len() => writeln!(
+                    f,
+                    "{:04}  {:<12}; -> {} (out of bounds)",
+                    index, instruction, target
+                )?,
+                Some(target) => writeln!(f, "{:04}  {:<12}; -> {:04}", index, instruction, target)?,
+                None => writeln!(f, "{:04}  {}", index, instruction)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Instruction that differs between two versions of a program
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Change {
+    pub index: usize,
+    pub before: Instruction,
+    pub after: Instruction,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-{:04}  {}", self.index, self.before)?;
+        write!(f, "+{:04}  {}", self.index, self.after)
+    }
+}
+
+/// Instructions changed between `before` and `after`, which hold programs of the same length
+pub fn diff(before: &[Instruction], after: &[Instruction]) -> Vec<Change> {
+    before
+        .iter()
+        .zip(after)
+        .enumerate()
+        .filter(|(_, (b, a))| b != a)
+        .map(|(index, (&before, &after))| Change {
+            index,
+            before,
+            after,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Vm, SAMPLE_PROGRAM};
+    use std::convert::TryFrom;
+
+    #[test]
+    pub fn test_round_trip() {
+        for line in SAMPLE_PROGRAM
+            .lines()
+            .chain(vec!["cpy 41 a", "jie a, +4", "jgz a b"])
+        {
+            let instruction = Instruction::try_from(line).unwrap();
+            assert_eq!(
+                Instruction::try_from(instruction.to_string().as_str()),
+                Ok(instruction)
+            );
+        }
+
+        assert_eq!(
+            Instruction::try_from("jie a, +4").unwrap().to_string(),
+            "jie a +4"
+        );
+    }
+
+    #[test]
+    pub fn test_listing() {
+        let vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        assert_eq!(
+            vm.listing().to_string(),
+            "0000  nop +0
+
+0001  acc +1
+0002  jmp +4      ; -> 0006
+
+0003  acc +3
+0004  jmp -3      ; -> 0001
+
+0005  acc -99
+
+0006  acc +1
+0007  jmp -4      ; -> 0003
+
+0008  acc +6
+"
+        );
+
+        let vm = Vm::try_from("jmp +2\njmp -2").unwrap();
+        assert_eq!(
+            vm.listing().to_string(),
+            "0000  jmp +2      ; -> end
+
+0001  jmp -2      ; -> -1 (out of bounds)
+"
+        );
+    }
+
+    #[test]
+    pub fn test_diff() {
+        let mut vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        assert_eq!(vm.changes(), vec![]);
+
+        vm.patch_instruction(7, Instruction::try_from("nop -4").unwrap());
+        let changes = vm.changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "-0007  jmp -4\n+0007  nop -4");
+    }
+}
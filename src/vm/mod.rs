@@ -1,63 +1,227 @@
+pub mod analysis;
 pub mod instruction;
+pub mod listing;
+pub mod observer;
 pub mod op;
+pub mod operand;
+pub mod repair;
 
 use instruction::Instruction;
-use std::{convert::TryFrom, num::ParseIntError};
+use listing::{Change, Listing};
+use observer::{Logger, Observer};
+use operand::Operand;
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    convert::TryFrom,
+    fmt,
+    num::ParseIntError,
+    sync::{Arc, Mutex},
+};
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq)]
+/// Line of a program that couldn't be parsed
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column of the offending text
+    pub column: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found `{}`",
+            self.line, self.column, self.expected, self.text
+        )
+    }
+}
+
+fn display_errors(errors: &[ParseError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("\n  {}", e))
+        .collect::<String>()
+}
+
+#[derive(Debug, Error, PartialEq, Clone)]
 pub enum VmError {
-    #[error("couldn't parse input")]
-    InputError,
+    #[error("couldn't parse program:{}", display_errors(.0))]
+    InvalidProgram(Vec<ParseError>),
+    #[error("couldn't parse instruction: {0}")]
+    InvalidInstruction(ParseError),
     #[error("invalid operation")]
     InvalidOperation,
     #[error("invalid number of operands")]
     InvalidNumberOfOperands,
+    #[error("operand must be a register")]
+    RegisterExpected,
     #[error("numeral parsing error")]
     ParsingError(#[from] ParseIntError),
-    #[error("nom parsing error: {0}")]
-    NomError(String),
+    #[error("jump from {ip} to {target} leaves the program")]
+    JumpOutOfBounds { ip: usize, target: isize },
+    #[error("division by zero at {ip}")]
+    DivisionByZero { ip: usize },
+    #[error("arithmetic overflow at {ip}")]
+    Overflow { ip: usize },
+    #[error("machine has already terminated")]
+    MachineTerminated,
+    #[error("no patch makes the program terminate")]
+    NoRepairFound,
 }
 
 pub struct Vm {
-    /// Instructions held in memory
-    pub instructions: Vec<Instruction>,
+    /// Instructions held in memory, shared with clones and snapshots until patched
+    pub instructions: Arc<Vec<Instruction>>,
     /// Accumulator value, can be modified with the `acc` instruction
     pub acc: isize,
+    /// Named registers, unset registers read as zero
+    pub registers: BTreeMap<char, isize>,
     /// Last instruction pointer
     pub lp: Option<usize>,
     /// Instruction pointer
     pub ip: usize,
-    /// Debug flag: if enabled, logs execution
-    debug: bool,
-    /// Used to reset the virtual machine's state if needed
-    snapshot: Vec<Instruction>,
+    /// Values waiting to be read by `rcv`
+    pub input: VecDeque<isize>,
+    /// Values sent by `snd` and not consumed yet
+    pub output: VecDeque<isize>,
+    /// Observers notified on every step
+    observers: Vec<Arc<Mutex<dyn Observer + Send>>>,
+    /// Program as loaded, used to reset the virtual machine's state if needed
+    original: Arc<Vec<Instruction>>,
+}
+
+/// Clones don't keep the observers, so that runs made on copies of the machine (e.g. by
+/// `repair`) aren't reported. Observers can be attached to the clone again if needed.
+impl Clone for Vm {
+    fn clone(&self) -> Self {
+        Vm {
+            instructions: self.instructions.clone(),
+            acc: self.acc,
+            registers: self.registers.clone(),
+            lp: self.lp,
+            ip: self.ip,
+            input: self.input.clone(),
+            output: self.output.clone(),
+            observers: vec![],
+            original: self.original.clone(),
+        }
+    }
+}
+
+/// Sample program of the 2020 day 8 puzzle, which loops forever unless its `jmp -4` becomes a
+/// `nop`. Shared by the tests of the library and of the binary, so it can't be `cfg(test)`.
+#[doc(hidden)]
+pub const SAMPLE_PROGRAM: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+/// State of a machine at some point of its execution, see `Vm::checkpoint`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Snapshot {
+    pub instructions: Arc<Vec<Instruction>>,
+    pub acc: isize,
+    pub registers: BTreeMap<char, isize>,
+    pub lp: Option<usize>,
+    pub ip: usize,
+    pub input: VecDeque<isize>,
+    pub output: VecDeque<isize>,
 }
 
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum VmState {
     Running,
+    /// A `rcv` instruction found the input queue empty and will be executed again on the next step
+    AwaitingInput,
+    Terminated,
+}
+
+/// Event on which `Vm::run_until` stops, on top of termination
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StopCondition {
+    /// Run until the program terminates, or its instruction pointer leaves the program
     Terminated,
+    /// Stop right before an instruction gets executed for the second time
+    InstructionRevisited,
+    /// Stop right before the program gets back to a state it has already been in, which means
+    /// it will never terminate. The accumulator is left out of the state as no instruction reads
+    /// it, but the number of pending inputs is part of it.
+    StateRevisited,
+    /// Stop after the given number of steps
+    StepLimit(usize),
+}
+
+/// State in which `Vm::run_until` stopped
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RunOutcome {
+    /// The instruction pointer reached the end of the program
+    Terminated { acc: isize },
+    /// The instruction at `ip` was about to be executed a second time, or in the same state
+    Looped { acc: isize, ip: usize },
+    /// The step limit was reached, `ip` being the next instruction to execute
+    StepLimitReached { acc: isize, ip: usize },
+    /// The `rcv` instruction at `ip` is waiting for an input value
+    AwaitingInput { acc: isize, ip: usize },
+    /// The jump at `ip` tried to leave the program without terminating it
+    OutOfBounds {
+        acc: isize,
+        ip: usize,
+        target: isize,
+    },
+}
+
+impl RunOutcome {
+    pub fn acc(&self) -> isize {
+        match *self {
+            RunOutcome::Terminated { acc }
+            | RunOutcome::Looped { acc, .. }
+            | RunOutcome::StepLimitReached { acc, .. }
+            | RunOutcome::AwaitingInput { acc, .. }
+            | RunOutcome::OutOfBounds { acc, .. } => acc,
+        }
+    }
 }
-use VmState::*;
 
 impl TryFrom<&str> for Vm {
     type Error = VmError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let instructions: Vec<Result<Instruction, _>> =
-            value.lines().map(Instruction::try_from).collect();
-        if instructions.iter().any(Result::is_err) {
-            return Err(VmError::InputError);
+        let mut instructions = vec![];
+        let mut errors = vec![];
+        for (index, line) in value.lines().enumerate() {
+            match Instruction::parse(line) {
+                Ok(instruction) => instructions.push(instruction),
+                Err(e) => errors.push(ParseError {
+                    line: index + 1,
+                    ..e
+                }),
+            }
         }
 
+        if !errors.is_empty() {
+            return Err(VmError::InvalidProgram(errors));
+        }
+
+        let instructions = Arc::new(instructions);
         Ok(Vm {
-            instructions: instructions.iter().flatten().cloned().collect(),
+            instructions: instructions.clone(),
             acc: 0,
+            registers: BTreeMap::new(),
             lp: None,
             ip: 0,
-            debug: false,
-            snapshot: instructions.iter().flatten().cloned().collect(),
+            input: VecDeque::new(),
+            output: VecDeque::new(),
+            observers: vec![],
+            original: instructions,
         })
     }
 }
@@ -65,31 +229,407 @@ impl TryFrom<&str> for Vm {
 impl Vm {
     pub fn from(input: &str, debug: bool) -> Result<Vm, VmError> {
         let mut vm = Vm::try_from(input)?;
-        vm.debug = debug;
+        if debug {
+            vm.observe(Logger);
+        }
         Ok(vm)
     }
 
+    /// Attaches `observer` to the machine, returning a handle to inspect it later on
+    pub fn observe<O: Observer + Send + 'static>(&mut self, observer: O) -> Arc<Mutex<O>> {
+        let observer = Arc::new(Mutex::new(observer));
+        self.observers.push(observer.clone());
+        observer
+    }
+
+    /// Detaches every observer
+    pub fn clear_observers(&mut self) {
+        self.observers.clear();
+    }
+
     pub fn reset(&mut self) {
         self.acc = 0;
+        self.registers.clear();
         self.ip = 0;
         self.lp = None;
-        self.instructions = self.snapshot.clone();
+        self.input.clear();
+        self.output.clear();
+        self.instructions = self.original.clone();
     }
 
-    pub fn step(&mut self) -> VmState {
+    /// Value of register `r`
+    pub fn register(&self, r: char) -> isize {
+        self.registers.get(&r).copied().unwrap_or(0)
+    }
+
+    /// Writes `value` into the register designated by `operand`
+    pub fn set_register(&mut self, operand: Operand, value: isize) -> Result<(), VmError> {
+        match operand {
+            Operand::Register(r) => {
+                self.registers.insert(r, value);
+                Ok(())
+            }
+            Operand::Immediate(_) => Err(VmError::RegisterExpected),
+        }
+    }
+
+    pub fn step(&mut self) -> Result<VmState, VmError> {
+        if self.ip >= self.instructions.len() {
+            return Err(VmError::MachineTerminated);
+        }
+
+        let instruction = self.instructions[self.ip];
+        for observer in &self.observers {
+            observer.lock().unwrap().before_step(self, &instruction);
+        }
+
         self.lp = Some(self.ip);
+        let state = match instruction.step(self) {
+            Ok(state) => state,
+            Err(e) => {
+                for observer in &self.observers {
+                    observer.lock().unwrap().on_error(self, &instruction, &e);
+                }
+                return Err(e);
+            }
+        };
+
+        for observer in &self.observers {
+            let mut observer = observer.lock().unwrap();
+            observer.after_step(self, &instruction);
+            if state == VmState::Terminated {
+                observer.on_terminate(self);
+            }
+        }
+
+        Ok(state)
+    }
 
-        let op = self.instructions[self.ip];
-        op.step(self);
+    /// Steps until the program terminates, or until `condition` is met
+    pub fn run_until(&mut self, condition: StopCondition) -> Result<RunOutcome, VmError> {
+        let mut visited = vec![false; self.instructions.len()];
+        let mut states = HashSet::new();
+        let mut steps = 0;
 
-        if self.ip == self.instructions.len() {
-            Terminated
-        } else {
-            Running
+        loop {
+            if self.ip == self.instructions.len() {
+                return Ok(RunOutcome::Terminated { acc: self.acc });
+            }
+
+            match condition {
+                StopCondition::InstructionRevisited if visited[self.ip] => {
+                    return Ok(RunOutcome::Looped {
+                        acc: self.acc,
+                        ip: self.ip,
+                    });
+                }
+                StopCondition::StateRevisited
+                    if !states.insert((self.ip, self.registers.clone(), self.input.len())) =>
+                {
+                    return Ok(RunOutcome::Looped {
+                        acc: self.acc,
+                        ip: self.ip,
+                    });
+                }
+                StopCondition::StepLimit(limit) if steps >= limit => {
+                    return Ok(RunOutcome::StepLimitReached {
+                        acc: self.acc,
+                        ip: self.ip,
+                    });
+                }
+                _ => {}
+            }
+
+            visited[self.ip] = true;
+            steps += 1;
+
+            match self.step() {
+                Err(VmError::JumpOutOfBounds { ip, target }) => {
+                    return Ok(RunOutcome::OutOfBounds {
+                        acc: self.acc,
+                        ip,
+                        target,
+                    })
+                }
+                Err(e) => return Err(e),
+                Ok(VmState::AwaitingInput) => {
+                    return Ok(RunOutcome::AwaitingInput {
+                        acc: self.acc,
+                        ip: self.ip,
+                    })
+                }
+                Ok(_) => {}
+            }
         }
     }
 
     pub fn patch_instruction(&mut self, index: usize, instruction: Instruction) {
-        self.instructions[index] = instruction;
+        Arc::make_mut(&mut self.instructions)[index] = instruction;
+    }
+
+    /// Saves the current state of the machine, which can be restored with `Vm::restore`.
+    /// Instructions are only copied once either the machine or the snapshot gets patched.
+    pub fn checkpoint(&self) -> Snapshot {
+        Snapshot {
+            instructions: self.instructions.clone(),
+            acc: self.acc,
+            registers: self.registers.clone(),
+            lp: self.lp,
+            ip: self.ip,
+            input: self.input.clone(),
+            output: self.output.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.instructions = snapshot.instructions.clone();
+        self.acc = snapshot.acc;
+        self.registers = snapshot.registers.clone();
+        self.lp = snapshot.lp;
+        self.ip = snapshot.ip;
+        self.input = snapshot.input.clone();
+        self.output = snapshot.output.clone();
+    }
+
+    /// Listing of the instructions currently held in memory
+    pub fn listing(&self) -> Listing<'_> {
+        Listing(&self.instructions)
+    }
+
+    /// Instructions patched since the program was loaded
+    pub fn changes(&self) -> Vec<Change> {
+        listing::diff(&self.original, &self.instructions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_run_until_revisited() {
+        let mut vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::InstructionRevisited),
+            Ok(RunOutcome::Looped { acc: 5, ip: 1 })
+        );
+    }
+
+    #[test]
+    pub fn test_run_until_terminated() {
+        let mut vm = Vm::try_from("acc +3\njmp +2\nacc +5\nacc -1").unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::Terminated),
+            Ok(RunOutcome::Terminated { acc: 2 })
+        );
+
+        let mut vm = Vm::try_from("acc +3\njmp +5\nacc -1").unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::InstructionRevisited),
+            Ok(RunOutcome::OutOfBounds {
+                acc: 3,
+                ip: 1,
+                target: 6
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_run_until_state_revisited() {
+        let mut vm = Vm::try_from("cpy 3 a\njie a, +3\nadd a 1\njmp -2\nacc a").unwrap();
+        assert_eq!(
+            vm.clone().run_until(StopCondition::InstructionRevisited),
+            Ok(RunOutcome::Looped { acc: 0, ip: 1 })
+        );
+        assert_eq!(
+            vm.run_until(StopCondition::StateRevisited),
+            Ok(RunOutcome::Terminated { acc: 4 })
+        );
+
+        let mut vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::StateRevisited),
+            Ok(RunOutcome::Looped { acc: 5, ip: 1 })
+        );
+
+        let mut vm = Vm::try_from("rcv a\njmp -1").unwrap();
+        vm.input.extend(&[1, 1]);
+        assert_eq!(
+            vm.run_until(StopCondition::StateRevisited),
+            Ok(RunOutcome::AwaitingInput { acc: 0, ip: 0 })
+        );
+    }
+
+    #[test]
+    pub fn test_run_until_step_limit() {
+        let mut vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::StepLimit(4)),
+            Ok(RunOutcome::StepLimitReached { acc: 2, ip: 7 })
+        );
+        assert_eq!(vm.run_until(StopCondition::StepLimit(2)).unwrap().acc(), 5);
+    }
+
+    #[test]
+    pub fn test_step_errors() {
+        let mut vm = Vm::try_from("acc +1\njmp -2").unwrap();
+        assert_eq!(vm.step(), Ok(VmState::Running));
+        assert_eq!(
+            vm.step(),
+            Err(VmError::JumpOutOfBounds { ip: 1, target: -1 })
+        );
+
+        let mut vm = Vm::try_from("jmp +1").unwrap();
+        assert_eq!(vm.step(), Ok(VmState::Terminated));
+        assert_eq!(vm.step(), Err(VmError::MachineTerminated));
+    }
+
+    #[test]
+    pub fn test_registers() {
+        let mut vm = Vm::try_from(
+            "cpy 41 a
+add a 1
+mul a 2
+mod a 5
+jgz a 2
+add b 7
+jio a, +2
+add b 1",
+        )
+        .unwrap();
+        assert_eq!(
+            vm.run_until(StopCondition::Terminated),
+            Ok(RunOutcome::Terminated { acc: 0 })
+        );
+        assert_eq!((vm.register('a'), vm.register('b')), (4, 1));
+
+        let mut vm = Vm::try_from("cpy 3 a\njie a, +3\nadd a 1\njmp -2\nacc a").unwrap();
+        assert_eq!(vm.run_until(StopCondition::Terminated).unwrap().acc(), 4);
+
+        vm.reset();
+        assert_eq!(vm.register('a'), 0);
+
+        let mut vm = Vm::try_from("mod a 0").unwrap();
+        assert_eq!(vm.step(), Err(VmError::DivisionByZero { ip: 0 }));
+    }
+
+    #[test]
+    pub fn test_io() {
+        let mut vm = Vm::try_from("rcv a\nmul a 2\nsnd a\nsnd 1").unwrap();
+        assert_eq!(vm.step(), Ok(VmState::AwaitingInput));
+        assert_eq!(
+            vm.run_until(StopCondition::Terminated),
+            Ok(RunOutcome::AwaitingInput { acc: 0, ip: 0 })
+        );
+
+        vm.input.push_back(21);
+        assert_eq!(
+            vm.run_until(StopCondition::Terminated),
+            Ok(RunOutcome::Terminated { acc: 0 })
+        );
+        assert_eq!(vm.output, vec![42, 1]);
+
+        vm.reset();
+        assert!(vm.output.is_empty());
+        assert!(Vm::try_from("rcv 1").is_err());
+    }
+
+    #[test]
+    pub fn test_overflow() {
+        let program = format!("cpy {} a\nmul a 2", isize::MAX);
+        let mut vm = Vm::try_from(program.as_str()).unwrap();
+        vm.step().unwrap();
+        assert_eq!(vm.step(), Err(VmError::Overflow { ip: 1 }));
+
+        let mut vm = Vm::try_from(format!("acc {}\nacc +1", isize::MAX).as_str()).unwrap();
+        vm.step().unwrap();
+        assert_eq!(vm.step(), Err(VmError::Overflow { ip: 1 }));
+
+        let mut vm = Vm::try_from(format!("jmp {}", isize::MAX).as_str()).unwrap();
+        assert!(matches!(vm.step(), Err(VmError::JumpOutOfBounds { .. })));
+    }
+
+    fn parse_error(line: usize, column: usize, text: &str, expected: &str) -> ParseError {
+        ParseError {
+            line,
+            column,
+            text: text.to_owned(),
+            expected: expected.to_owned(),
+        }
+    }
+
+    #[test]
+    pub fn test_invalid_program() {
+        assert_eq!(
+            Vm::try_from("nop +0\nacc 1 2\njmp +1\nfoo +3").err(),
+            Some(VmError::InvalidProgram(vec![
+                parse_error(2, 7, "2", "end of line"),
+                parse_error(4, 1, "foo", "an operation"),
+            ]))
+        );
+    }
+
+    #[test]
+    pub fn test_observers() {
+        let mut vm = Vm::try_from("acc +3\njmp +2\nacc +5\nacc -1").unwrap();
+        let trace = vm.observe(observer::Trace::default());
+
+        vm.run_until(StopCondition::Terminated).unwrap();
+        let trace = trace.lock().unwrap();
+        assert_eq!(
+            trace
+                .entries
+                .iter()
+                .map(|e| (e.ip, e.instruction.to_string(), e.acc))
+                .collect::<Vec<_>>(),
+            [
+                (0, "acc +3".to_owned(), 3),
+                (1, "jmp +2".to_owned(), 3),
+                (3, "acc -1".to_owned(), 2)
+            ]
+        );
+        assert!(trace.terminated);
+
+        // Clones don't notify the original's observers
+        let mut clone = vm.clone();
+        clone.reset();
+        clone.run_until(StopCondition::Terminated).unwrap();
+        assert_eq!(trace.entries.len(), 3);
+    }
+
+    #[test]
+    pub fn test_observer_error() {
+        let mut vm = Vm::try_from("acc +1\njmp -5").unwrap();
+        let trace = vm.observe(observer::Trace::default());
+
+        vm.step().unwrap();
+        let error = VmError::JumpOutOfBounds { ip: 1, target: -4 };
+        assert_eq!(vm.step(), Err(error.clone()));
+
+        let trace = trace.lock().unwrap();
+        assert_eq!(trace.entries.len(), 1);
+        assert_eq!(trace.error, Some(error));
+    }
+
+    #[test]
+    pub fn test_checkpoint() {
+        let mut vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        vm.run_until(StopCondition::StepLimit(3)).unwrap();
+        let snapshot = vm.checkpoint();
+        assert!(Arc::ptr_eq(&snapshot.instructions, &vm.instructions));
+
+        vm.patch_instruction(7, Instruction::try_from("nop -4").unwrap());
+        assert_eq!(
+            vm.run_until(StopCondition::InstructionRevisited),
+            Ok(RunOutcome::Terminated { acc: 8 })
+        );
+
+        vm.restore(&snapshot);
+        assert_eq!((vm.ip, vm.lp, vm.acc), (6, Some(2), 1));
+        assert_eq!(vm.instructions[7].op, op::Op::Jmp);
+        assert_eq!(
+            vm.run_until(StopCondition::InstructionRevisited),
+            Ok(RunOutcome::Looped { acc: 6, ip: 6 })
+        );
     }
 }
@@ -0,0 +1,64 @@
+use super::{instruction::Instruction, Vm, VmError};
+
+/// Callbacks invoked by `Vm::step`. Every callback does nothing by default.
+pub trait Observer {
+    /// Called right before `instruction` gets executed, `vm.ip` being its address
+    fn before_step(&mut self, _vm: &Vm, _instruction: &Instruction) {}
+
+    /// Called right after `instruction` got executed, `vm.lp` being its address
+    fn after_step(&mut self, _vm: &Vm, _instruction: &Instruction) {}
+
+    /// Called once the instruction pointer reached the end of the program
+    fn on_terminate(&mut self, _vm: &Vm) {}
+
+    /// Called instead of `after_step` when executing `instruction` failed
+    fn on_error(&mut self, _vm: &Vm, _instruction: &Instruction, _error: &VmError) {}
+}
+
+/// Prints every executed instruction
+pub struct Logger;
+
+impl Observer for Logger {
+    fn before_step(&mut self, _vm: &Vm, instruction: &Instruction) {
+        println!("Stepping with: {:?}", instruction);
+    }
+
+    fn on_error(&mut self, _vm: &Vm, instruction: &Instruction, error: &VmError) {
+        println!("Failed to execute {:?}: {}", instruction, error);
+    }
+}
+
+/// Executed instruction, along with the machine state right after it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TraceEntry {
+    pub ip: usize,
+    pub instruction: Instruction,
+    pub acc: isize,
+}
+
+/// Records every executed instruction
+#[derive(Debug, Default)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+    pub terminated: bool,
+    /// Error that stopped the machine, if any
+    pub error: Option<VmError>,
+}
+
+impl Observer for Trace {
+    fn after_step(&mut self, vm: &Vm, instruction: &Instruction) {
+        self.entries.push(TraceEntry {
+            ip: vm.lp.unwrap_or_default(),
+            instruction: *instruction,
+            acc: vm.acc,
+        });
+    }
+
+    fn on_terminate(&mut self, _vm: &Vm) {
+        self.terminated = true;
+    }
+
+    fn on_error(&mut self, _vm: &Vm, _instruction: &Instruction, error: &VmError) {
+        self.error = Some(error.clone());
+    }
+}
@@ -1,4 +1,4 @@
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt};
 
 use super::VmError;
 
@@ -11,10 +11,49 @@ pub enum Op {
     Acc,
     /// Jump
     Jmp,
+    /// Copy a value into a register
+    Cpy,
+    /// Add a value to a register
+    Add,
+    /// Multiply a register by a value
+    Mul,
+    /// Replace a register with its remainder by a value
+    Mod,
+    /// Jump if the value is even
+    Jie,
+    /// Jump if the value is one
+    Jio,
+    /// Jump if the value is greater than zero
+    Jgz,
+    /// Send a value to the output queue
+    Snd,
+    /// Move the next value of the input queue into a register, waiting for one if needed
+    Rcv,
 }
 
 use Op::*;
 
+impl Op {
+    /// Number of operands expected by the operation
+    pub fn arity(self) -> usize {
+        match self {
+            Nop | Acc | Jmp | Snd | Rcv => 1,
+            Cpy | Add | Mul | Mod | Jie | Jio | Jgz => 2,
+        }
+    }
+
+    /// Whether the operation jumps when its condition operand holds `value`
+    pub fn jumps_if(self, value: isize) -> bool {
+        match self {
+            Jmp => true,
+            Jie => value % 2 == 0,
+            Jio => value == 1,
+            Jgz => value > 0,
+            _ => false,
+        }
+    }
+}
+
 impl TryFrom<&str> for Op {
     type Error = VmError;
 
@@ -23,7 +62,37 @@ impl TryFrom<&str> for Op {
             "nop" => Ok(Nop),
             "acc" => Ok(Acc),
             "jmp" => Ok(Jmp),
+            "cpy" => Ok(Cpy),
+            "add" => Ok(Add),
+            "mul" => Ok(Mul),
+            "mod" => Ok(Mod),
+            "jie" => Ok(Jie),
+            "jio" => Ok(Jio),
+            "jgz" => Ok(Jgz),
+            "snd" => Ok(Snd),
+            "rcv" => Ok(Rcv),
             _ => Err(VmError::InvalidOperation),
         }
     }
 }
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = match self {
+            Nop => "nop",
+            Acc => "acc",
+            Jmp => "jmp",
+            Cpy => "cpy",
+            Add => "add",
+            Mul => "mul",
+            Mod => "mod",
+            Jie => "jie",
+            Jio => "jio",
+            Jgz => "jgz",
+            Snd => "snd",
+            Rcv => "rcv",
+        };
+
+        f.pad(mnemonic)
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use super::Vm;
+
+/// Operand of an instruction: either a named register or an immediate value
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Operand {
+    /// Register, named by a single lowercase letter
+    Register(char),
+    /// Immediate value
+    Immediate(isize),
+}
+
+use Operand::*;
+
+impl Operand {
+    /// Value of the operand in the current state of `vm`
+    pub fn value(self, vm: &Vm) -> isize {
+        match self {
+            Register(r) => vm.register(r),
+            Immediate(v) => v,
+        }
+    }
+
+    pub fn is_register(self) -> bool {
+        matches!(self, Register(_))
+    }
+}
+
+impl From<isize> for Operand {
+    fn from(value: isize) -> Self {
+        Immediate(value)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register(r) => write!(f, "{}", r),
+            Immediate(v) => write!(f, "{:+}", v),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+use super::{
+    instruction::Instruction, op::Op, operand::Operand, RunOutcome, StopCondition, Vm, VmError,
+};
+
+/// Change that may be applied to a single instruction while searching for a repair
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mutation {
+    /// Replace `from` with `to`, keeping the operands
+    SwapOp { from: Op, to: Op },
+    /// Replace the left operand of `op` instructions with `lhs`
+    SetOperand { op: Op, lhs: Operand },
+}
+
+impl Mutation {
+    /// Mutated instruction, if the mutation applies to `instruction`
+    pub fn apply(self, instruction: Instruction) -> Option<Instruction> {
+        match self {
+            Mutation::SwapOp { from, to } if instruction.op == from => Some(Instruction {
+                op: to,
+                ..instruction
+            }),
+            Mutation::SetOperand { op, lhs } if instruction.op == op && instruction.lhs != lhs => {
+                Some(Instruction { lhs, ..instruction })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Instruction to write at `index`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Patch {
+    pub index: usize,
+    pub instruction: Instruction,
+}
+
+/// Patch making the program terminate, along with the final accumulator value
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Repair {
+    pub patch: Patch,
+    pub acc: isize,
+}
+
+/// Every single-instruction patch allowed by `mutations`, ordered by index
+pub fn candidates(instructions: &[Instruction], mutations: &[Mutation]) -> Vec<Patch> {
+    instructions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &instruction)| {
+            mutations.iter().flat_map(move |m| {
+                m.apply(instruction)
+                    .map(|instruction| Patch { index, instruction })
+            })
+        })
+        .collect()
+}
+
+/// Puts `vm` back at the start of the program it has loaded, with empty registers and queues
+fn rewind(vm: &mut Vm) {
+    vm.acc = 0;
+    vm.registers.clear();
+    vm.input.clear();
+    vm.output.clear();
+    vm.ip = 0;
+    vm.lp = None;
+}
+
+/// Runs the program from the start with `patch` applied
+pub fn try_patch(vm: &Vm, patch: Patch) -> Result<Option<Repair>, VmError> {
+    let mut vm = vm.clone();
+    rewind(&mut vm);
+    vm.patch_instruction(patch.index, patch.instruction);
+
+    match vm.run_until(StopCondition::StateRevisited)? {
+        RunOutcome::Terminated { acc } => Ok(Some(Repair { patch, acc })),
+        _ => Ok(None),
+    }
+}
+
+/// Finds every single-instruction patch allowed by `mutations` that makes the program
+/// currently loaded in `vm` terminate. `vm` itself is left untouched.
+pub fn repair(vm: &Vm, mutations: &[Mutation]) -> Result<Vec<Repair>, VmError> {
+    candidates(&vm.instructions, mutations)
+        .into_iter()
+        .filter_map(|p| try_patch(vm, p).transpose())
+        .collect()
+}
+
+/// Same as `repair`, but only tries patching the instructions the original program executes:
+/// every patch resumes from a checkpoint taken right before the patched instruction runs,
+/// instead of replaying the program from the start.
+pub fn fork_repair(vm: &Vm, mutations: &[Mutation]) -> Result<Vec<Repair>, VmError> {
+    let mut vm = vm.clone();
+    rewind(&mut vm);
+
+    let mut states = HashSet::new();
+    let mut repairs = vec![];
+    while vm.ip < vm.instructions.len()
+        && states.insert((vm.ip, vm.registers.clone(), vm.input.len()))
+    {
+        let instruction = vm.instructions[vm.ip];
+        for patched in mutations.iter().filter_map(|m| m.apply(instruction)) {
+            let fork = vm.checkpoint();
+            vm.patch_instruction(fork.ip, patched);
+
+            if let RunOutcome::Terminated { acc } = vm.run_until(StopCondition::StateRevisited)? {
+                repairs.push(Repair {
+                    patch: Patch {
+                        index: fork.ip,
+                        instruction: patched,
+                    },
+                    acc,
+                });
+            }
+
+            vm.restore(&fork);
+        }
+
+        match vm.step() {
+            Err(VmError::JumpOutOfBounds { .. }) => break,
+            result => result?,
+        };
+    }
+
+    repairs.sort_by_key(|r| r.patch.index);
+    Ok(repairs)
+}
+
+/// Same as `repair`, trying the patches in parallel
+pub fn par_repair(vm: &Vm, mutations: &[Mutation]) -> Result<Vec<Repair>, VmError> {
+    candidates(&vm.instructions, mutations)
+        .into_par_iter()
+        .filter_map(|p| try_patch(vm, p).transpose())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{op::Op::*, operand::Operand::*, SAMPLE_PROGRAM};
+    use std::convert::TryFrom;
+
+    const SWAPS: [Mutation; 2] = [
+        Mutation::SwapOp { from: Jmp, to: Nop },
+        Mutation::SwapOp { from: Nop, to: Jmp },
+    ];
+
+    #[test]
+    pub fn test_candidates() {
+        let vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        let indexes: Vec<usize> = candidates(&vm.instructions, &SWAPS)
+            .iter()
+            .map(|p| p.index)
+            .collect();
+
+        assert_eq!(indexes, [0, 2, 4, 7]);
+    }
+
+    #[test]
+    pub fn test_repair() {
+        let vm = Vm::try_from(SAMPLE_PROGRAM).unwrap();
+        let expected = vec![Repair {
+            patch: Patch {
+                index: 7,
+                instruction: Instruction {
+                    op: Nop,
+                    lhs: Immediate(-4),
+                    rhs: None,
+                },
+            },
+            acc: 8,
+        }];
+
+        assert_eq!(repair(&vm, &SWAPS), Ok(expected.clone()));
+        assert_eq!(par_repair(&vm, &SWAPS), Ok(expected.clone()));
+        assert_eq!(fork_repair(&vm, &SWAPS), Ok(expected));
+        assert_eq!(vm.instructions[7].op, Jmp);
+    }
+
+    #[test]
+    pub fn test_repair_operands() {
+        let vm = Vm::try_from("acc +2\njmp -1\nacc +3").unwrap();
+        let repairs = repair(
+            &vm,
+            &[Mutation::SetOperand {
+                op: Jmp,
+                lhs: Immediate(1),
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].acc, 5);
+        assert_eq!(repair(&vm, &[]), Ok(vec![]));
+    }
+
+    #[test]
+    pub fn test_repair_revisiting() {
+        let vm = Vm::try_from("cpy 3 a\njie a, +3\nadd a 1\njmp -2\nacc a\njmp +0").unwrap();
+        let expected = vec![Repair {
+            patch: Patch {
+                index: 5,
+                instruction: Instruction {
+                    op: Nop,
+                    lhs: Immediate(0),
+                    rhs: None,
+                },
+            },
+            acc: 4,
+        }];
+
+        assert_eq!(repair(&vm, &SWAPS), Ok(expected.clone()));
+        assert_eq!(par_repair(&vm, &SWAPS), Ok(expected.clone()));
+        assert_eq!(fork_repair(&vm, &SWAPS), Ok(expected));
+    }
+
+    #[test]
+    pub fn test_repair_clears_queues() {
+        let mut vm = Vm::try_from("rcv a\nacc a\njmp +0").unwrap();
+        vm.input.push_back(5);
+        vm.output.push_back(1);
+
+        assert_eq!(repair(&vm, &SWAPS), Ok(vec![]));
+        assert_eq!(fork_repair(&vm, &SWAPS), Ok(vec![]));
+        assert_eq!(vm.input, [5]);
+    }
+}